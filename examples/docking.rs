@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use bevy_mod_imgui::prelude::*;
+
+fn main() {
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::srgba(0.2, 0.2, 0.2, 1.0)))
+        .add_plugins(DefaultPlugins)
+        .add_systems(Startup, setup)
+        .add_plugins(bevy_mod_imgui::ImguiPlugin {
+            ini_filename: Some("docking.ini".into()),
+            docking: true,
+            ..default()
+        })
+        .add_systems(Update, imgui_example_ui);
+
+    app.run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // plane
+    commands.spawn((
+        Mesh3d(meshes.add(Plane3d::default().mesh().size(5.0, 5.0))),
+        MeshMaterial3d(materials.add(Color::srgb(0.3, 0.5, 0.3))),
+    ));
+    // cube
+    commands.spawn((
+        Mesh3d(meshes.add(Cuboid::default().mesh())),
+        MeshMaterial3d(materials.add(Color::srgb(0.8, 0.7, 0.6))),
+        Transform::from_xyz(0.0, 0.5, 0.0),
+    ));
+    // light
+    commands.spawn((
+        PointLight {
+            shadows_enabled: true,
+            ..default()
+        },
+        Transform::from_xyz(4.0, 8.0, 4.0),
+    ));
+    // camera
+    commands.spawn((
+        Transform::from_xyz(1.7, 1.7, 2.0).looking_at(Vec3::new(0.0, 0.3, 0.0), Vec3::Y),
+        Camera3d::default(),
+    ));
+}
+
+fn imgui_example_ui(mut context: NonSendMut<ImguiContext>) {
+    let ui = context.ui();
+
+    // The scene remains visible in the central node of the dockspace
+    imgui_dockspace_over_window(ui);
+
+    ui.window("Hierarchy")
+        .size([250.0, 400.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            ui.text("Drag this window by its title bar");
+            ui.text("to dock it to an edge of the window.");
+        });
+
+    ui.window("Inspector")
+        .size([250.0, 400.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            let mouse_pos = ui.io().mouse_pos;
+            ui.text(format!(
+                "Mouse Position: ({:.1},{:.1})",
+                mouse_pos[0], mouse_pos[1]
+            ));
+        });
+}
@@ -0,0 +1,135 @@
+//! Shows how to draw custom wgpu content inside an ImGui window with a draw callback.
+
+use std::sync::OnceLock;
+
+use bevy::prelude::*;
+use bevy::render::renderer::RenderDevice;
+use bevy_mod_imgui::prelude::*;
+
+#[derive(Resource)]
+struct ImguiState {
+    triangle_callback: ImguiDrawCallbackId,
+}
+
+// Draws a single coloured triangle that fills the clip rect it is given
+#[derive(Default)]
+struct TriangleCallback {
+    pipeline: OnceLock<(wgpu::TextureFormat, wgpu::RenderPipeline)>,
+}
+
+const TRIANGLE_SHADER: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var positions = array<vec2<f32>, 3>(vec2(0.0, 1.0), vec2(-1.0, -1.0), vec2(1.0, -1.0));
+    var colors = array<vec3<f32>, 3>(vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, 1.0));
+    var out: VertexOutput;
+    out.position = vec4(positions[index], 0.0, 1.0);
+    out.color = colors[index];
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4(in.color, 1.0);
+}
+"#;
+
+impl TriangleCallback {
+    fn create_pipeline(
+        device: &RenderDevice,
+        texture_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let device = device.wgpu_device();
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("triangle shader"),
+            source: wgpu::ShaderSource::Wgsl(TRIANGLE_SHADER.into()),
+        });
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("triangle pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(texture_format.into())],
+            }),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
+impl ImguiDrawCallback for TriangleCallback {
+    fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>, info: &ImguiDrawCallbackInfo) {
+        let (texture_format, pipeline) = self.pipeline.get_or_init(|| {
+            (
+                info.texture_format,
+                Self::create_pipeline(info.device, info.texture_format),
+            )
+        });
+        if *texture_format != info.texture_format {
+            return;
+        }
+
+        // Fit the viewport to the visible part of the clip rect
+        let [min_x, min_y, max_x, max_y] = info.clip_rect;
+        let min_x = min_x.max(0.0);
+        let min_y = min_y.max(0.0);
+        let max_x = max_x.min(info.framebuffer_size[0]);
+        let max_y = max_y.min(info.framebuffer_size[1]);
+        render_pass.set_viewport(min_x, min_y, max_x - min_x, max_y - min_y, 0.0, 1.0);
+        render_pass.set_pipeline(pipeline);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+fn main() {
+    App::new()
+        .insert_resource(ClearColor(Color::srgba(0.2, 0.2, 0.2, 1.0)))
+        .add_plugins(DefaultPlugins)
+        .add_plugins(bevy_mod_imgui::ImguiPlugin::default())
+        .add_systems(Startup, setup)
+        .add_systems(Update, imgui_example_ui)
+        .run();
+}
+
+fn setup(mut commands: Commands, mut context: NonSendMut<ImguiContext>) {
+    commands.spawn(Camera3d::default());
+    commands.insert_resource(ImguiState {
+        triangle_callback: context.register_draw_callback(TriangleCallback::default()),
+    });
+}
+
+fn imgui_example_ui(mut context: NonSendMut<ImguiContext>, state: Res<ImguiState>) {
+    let ui = context.ui();
+    ui.window("Draw Callback")
+        .size([300.0, 300.0], imgui::Condition::FirstUseEver)
+        .position([20.0, 20.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            ui.text("This triangle is drawn with a custom wgpu pipeline");
+
+            // Clip the callback to a region of the window, and reserve space for it
+            let size = ui.content_region_avail();
+            let min = ui.cursor_screen_pos();
+            let max = [min[0] + size[0], min[1] + size[1]];
+            let draw_list = ui.get_window_draw_list();
+            draw_list.with_clip_rect_intersect(min, max, || {
+                state.triangle_callback.add_to_window_draw_list(ui);
+            });
+            ui.dummy(size);
+        });
+}
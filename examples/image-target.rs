@@ -0,0 +1,109 @@
+//! Shows how to render ImGui into an image, which is then displayed on a cube in the scene.
+
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+
+use bevy_mod_imgui::prelude::*;
+
+#[derive(Resource)]
+struct ImguiState {
+    image_target: Entity,
+    counter: i32,
+}
+
+// Marks the cube that displays the ImGui image
+#[derive(Component)]
+struct MonitorCube;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(bevy_mod_imgui::ImguiPlugin::default())
+        .add_systems(Startup, setup)
+        .add_systems(Update, (imgui_example_ui, rotator_system))
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    // Set up an image for ImGui to render to
+    let size = Extent3d {
+        width: 512,
+        height: 512,
+        ..default()
+    };
+
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    let image_handle = images.add(image);
+
+    // The entity that owns the ImGui context that renders to the image
+    let image_target = commands
+        .spawn(ImguiImageTarget::new(image_handle.clone()))
+        .id();
+
+    commands.insert_resource(ImguiState {
+        image_target,
+        counter: 0,
+    });
+
+    // A cube that displays the image
+    commands.spawn((
+        Mesh3d(meshes.add(Cuboid::new(4.0, 4.0, 4.0))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color_texture: Some(image_handle),
+            unlit: true,
+            ..default()
+        })),
+        MonitorCube,
+    ));
+
+    commands.spawn((
+        Camera3d::default(),
+        Transform::from_translation(Vec3::new(0.0, 0.0, 12.0)).looking_at(Vec3::ZERO, Vec3::Y),
+    ));
+}
+
+/// Rotates the cube
+fn rotator_system(time: Res<Time>, mut query: Query<&mut Transform, With<MonitorCube>>) {
+    for mut transform in &mut query {
+        transform.rotate_x(0.5 * time.delta_secs());
+        transform.rotate_y(0.7 * time.delta_secs());
+    }
+}
+
+fn imgui_example_ui(mut context: NonSendMut<ImguiContext>, mut state: ResMut<ImguiState>) {
+    let Some(ui) = context.window_ui(state.image_target) else {
+        return;
+    };
+    ui.window("Image Target")
+        .size([400.0, 200.0], imgui::Condition::FirstUseEver)
+        .position([50.0, 50.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            ui.text("This UI is rendered into an image");
+            ui.separator();
+            ui.text(format!("Frame: {}", state.counter));
+            imgui::ProgressBar::new((state.counter % 100) as f32 / 100.0).build(ui);
+        });
+    state.counter += 1;
+}
@@ -0,0 +1,63 @@
+use bevy::{prelude::*, render::camera::RenderTarget, window::WindowRef};
+use bevy_mod_imgui::prelude::*;
+
+#[derive(Resource)]
+struct ImguiState {
+    second_window: Entity,
+}
+
+fn main() {
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::srgba(0.2, 0.2, 0.2, 1.0)))
+        .add_plugins(DefaultPlugins)
+        .add_plugins(bevy_mod_imgui::ImguiPlugin::default())
+        .add_systems(Startup, setup)
+        .add_systems(Update, imgui_example_ui);
+    app.run();
+}
+
+fn setup(mut commands: Commands) {
+    // camera for the primary window
+    commands.spawn(Camera3d::default());
+
+    // a second window, with its own ImGui context
+    let second_window = commands
+        .spawn((
+            bevy::window::Window {
+                title: "Second window".to_owned(),
+                ..default()
+            },
+            ImguiWindow::default(),
+        ))
+        .id();
+
+    // camera for the second window
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            target: RenderTarget::Window(WindowRef::Entity(second_window)),
+            ..default()
+        },
+    ));
+
+    commands.insert_resource(ImguiState { second_window });
+}
+
+fn imgui_example_ui(mut context: NonSendMut<ImguiContext>, state: Res<ImguiState>) {
+    let ui = context.ui();
+    ui.window("Primary window")
+        .size([300.0, 100.0], imgui::Condition::FirstUseEver)
+        .position([0.0, 0.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            ui.text("This window belongs to the primary window's context");
+        });
+
+    if let Some(ui) = context.window_ui(state.second_window) {
+        ui.window("Second window")
+            .size([300.0, 100.0], imgui::Condition::FirstUseEver)
+            .position([0.0, 0.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text("This window belongs to the second window's context");
+            });
+    }
+}
@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use bevy_mod_imgui::prelude::*;
+
+#[derive(Resource)]
+struct ImguiState {
+    demo_window_open: bool,
+}
+
+fn main() {
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::srgba(0.2, 0.2, 0.2, 1.0)))
+        .insert_resource(ImguiState {
+            demo_window_open: true,
+        })
+        .add_plugins(DefaultPlugins)
+        .add_plugins(bevy_mod_imgui::ImguiPlugin::default())
+        .add_systems(Startup, |mut commands: Commands| {
+            commands.spawn(Camera3d::default());
+        })
+        .add_systems(Update, (imgui_example_ui, imgui_queued_ui));
+    app.run();
+}
+
+// Systems with the ImguiUi system parameter submit UI directly, on the main thread
+fn imgui_example_ui(mut ui: ImguiUi, mut state: ResMut<ImguiState>) {
+    // The UI is skipped rather than panicking if no imgui frame is being rendered
+    let Some(ui) = ui.try_ui() else {
+        return;
+    };
+    ui.window("ImguiUi")
+        .size([300.0, 100.0], imgui::Condition::FirstUseEver)
+        .position([0.0, 0.0], imgui::Condition::FirstUseEver)
+        .build(|| {
+            ui.text("Submitted with the ImguiUi system parameter");
+            ui.checkbox("Show demo window", &mut state.demo_window_open);
+        });
+
+    if state.demo_window_open {
+        ui.show_demo_window(&mut state.demo_window_open);
+    }
+}
+
+// Systems with the ImguiUiQueue resource can run in parallel with other systems, and queue UI
+// that is submitted at the end of the frame
+fn imgui_queued_ui(queue: Res<ImguiUiQueue>, time: Res<Time>) {
+    let elapsed = time.elapsed_secs();
+    queue.add(move |ui| {
+        ui.window("ImguiUiQueue")
+            .size([300.0, 100.0], imgui::Condition::FirstUseEver)
+            .position([0.0, 110.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text("Queued from a system that can run in parallel");
+                ui.text(format!("Elapsed: {elapsed:.1}s"));
+            });
+    });
+}
@@ -0,0 +1,95 @@
+// Clipboard integration for ImGui's text widgets
+
+use std::sync::{Arc, Mutex};
+
+use imgui::ClipboardBackend;
+
+/// The clipboard used by ImGui's text widgets, shared by every ImGui context.
+///
+/// By default this is an in-memory clipboard, which also works in headless apps. Enable the
+/// `clipboard` cargo feature to use the operating system's clipboard instead. Any other
+/// `imgui::ClipboardBackend` can be used via `ImguiClipboard::new`.
+#[derive(Clone)]
+pub struct ImguiClipboard(Arc<Mutex<dyn ClipboardBackend + Send>>);
+
+impl ImguiClipboard {
+    /// Creates a clipboard that forwards to the given backend.
+    pub fn new(backend: impl ClipboardBackend + Send) -> Self {
+        Self(Arc::new(Mutex::new(backend)))
+    }
+
+    /// Creates a clipboard that is only shared within the app.
+    pub fn memory() -> Self {
+        Self::new(MemoryClipboard::default())
+    }
+
+    /// Creates a clipboard that uses the operating system's clipboard, falling back to an
+    /// in-memory clipboard if the operating system's clipboard is unavailable.
+    #[cfg(feature = "clipboard")]
+    pub fn system() -> Self {
+        match arboard::Clipboard::new() {
+            Ok(clipboard) => Self::new(SystemClipboard(clipboard)),
+            Err(error) => {
+                log::warn!("Could not access the system clipboard, using an in-memory clipboard instead: {error}");
+                Self::memory()
+            }
+        }
+    }
+
+    // Creates a backend for a single ImGui context that forwards to this clipboard
+    pub(crate) fn backend(&self) -> impl ClipboardBackend {
+        SharedClipboard(self.clone())
+    }
+}
+
+impl Default for ImguiClipboard {
+    fn default() -> Self {
+        #[cfg(feature = "clipboard")]
+        return Self::system();
+
+        #[cfg(not(feature = "clipboard"))]
+        return Self::memory();
+    }
+}
+
+// Each ImGui context owns its clipboard backend, so this forwards to the shared clipboard
+struct SharedClipboard(ImguiClipboard);
+
+impl ClipboardBackend for SharedClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.0 .0.lock().unwrap().get()
+    }
+
+    fn set(&mut self, value: &str) {
+        self.0 .0.lock().unwrap().set(value)
+    }
+}
+
+#[derive(Default)]
+struct MemoryClipboard(Option<String>);
+
+impl ClipboardBackend for MemoryClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.0.clone()
+    }
+
+    fn set(&mut self, value: &str) {
+        self.0 = Some(value.to_owned());
+    }
+}
+
+#[cfg(feature = "clipboard")]
+struct SystemClipboard(arboard::Clipboard);
+
+#[cfg(feature = "clipboard")]
+impl ClipboardBackend for SystemClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.0.get_text().ok()
+    }
+
+    fn set(&mut self, value: &str) {
+        if let Err(error) = self.0.set_text(value) {
+            log::warn!("Could not set the system clipboard: {error}");
+        }
+    }
+}
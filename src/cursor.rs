@@ -0,0 +1,100 @@
+// Synchronisation of ImGui's mouse cursor to the cursor of each Bevy window
+
+use bevy::{prelude::*, window::SystemCursorIcon, winit::cursor::CursorIcon};
+use std::collections::{HashMap, HashSet};
+
+use crate::ImguiContext;
+
+// Maps an ImGui mouse cursor to the corresponding Bevy cursor icon, or None to hide the cursor
+fn cursor_icon(cursor: Option<imgui::MouseCursor>) -> Option<CursorIcon> {
+    let icon = match cursor? {
+        imgui::MouseCursor::Arrow => SystemCursorIcon::Default,
+        imgui::MouseCursor::TextInput => SystemCursorIcon::Text,
+        imgui::MouseCursor::ResizeAll => SystemCursorIcon::Move,
+        imgui::MouseCursor::ResizeNS => SystemCursorIcon::NsResize,
+        imgui::MouseCursor::ResizeEW => SystemCursorIcon::EwResize,
+        imgui::MouseCursor::ResizeNESW => SystemCursorIcon::NeswResize,
+        imgui::MouseCursor::ResizeNWSE => SystemCursorIcon::NwseResize,
+        imgui::MouseCursor::Hand => SystemCursorIcon::Pointer,
+        imgui::MouseCursor::NotAllowed => SystemCursorIcon::NotAllowed,
+    };
+    Some(icon.into())
+}
+
+// The game's cursor icon and visibility of a window, saved while ImGui controls its cursor
+pub(crate) struct SavedCursor {
+    icon: Option<CursorIcon>,
+    visible: bool,
+}
+
+// Sets the cursor icon of each window to the mouse cursor that ImGui wants while ImGui is using
+// the mouse, hiding the cursor if ImGui wants no cursor. The game's cursor icon and visibility are
+// saved when ImGui takes over the cursor, and restored when ImGui stops using the mouse, so the
+// game remains free to set the cursor at other times. The windows of ImGui viewports use the
+// cursor of the context that owns them.
+pub(crate) fn imgui_update_cursor_system(
+    mut commands: Commands,
+    mut context: NonSendMut<ImguiContext>,
+    mut windows: Query<(&mut Window, Option<&CursorIcon>)>,
+    mut saved_cursors: Local<HashMap<Entity, SavedCursor>>,
+) {
+    let context = context.as_mut();
+
+    // The windows of each context, whose saved cursors are kept
+    let mut context_windows = HashSet::new();
+    for (entity, target_context) in context.targets.iter_mut() {
+        #[cfg(feature = "docking")]
+        let viewport_windows: Vec<_> = target_context
+            .viewports
+            .as_ref()
+            .map(|viewports| viewports.borrow().windows().collect())
+            .unwrap_or_default();
+        #[cfg(not(feature = "docking"))]
+        let viewport_windows = Vec::new();
+        let entities: Vec<_> = std::iter::once(*entity).chain(viewport_windows).collect();
+        context_windows.extend(entities.iter().copied());
+
+        let Some(ui) = target_context.ui else {
+            continue; // No frame was started for this target
+        };
+        target_context.activate();
+
+        let ctx = target_context.ctx.get_mut().unwrap();
+        let io = ctx.io();
+        let wants_cursor = io.want_capture_mouse
+            && !io
+                .config_flags
+                .contains(imgui::ConfigFlags::NO_MOUSE_CURSOR_CHANGE);
+        let icon = cursor_icon(unsafe { ui.as_ref() }.mouse_cursor());
+
+        for entity in entities {
+            // Image targets are not windows, so never have a cursor (and never use the mouse)
+            let Ok((mut window, current)) = windows.get_mut(entity) else {
+                continue;
+            };
+
+            if wants_cursor {
+                saved_cursors.entry(entity).or_insert_with(|| SavedCursor {
+                    icon: current.cloned(),
+                    visible: window.cursor_options.visible,
+                });
+                if window.cursor_options.visible != icon.is_some() {
+                    window.cursor_options.visible = icon.is_some();
+                }
+                if let Some(icon) = icon.as_ref().filter(|icon| current != Some(*icon)) {
+                    commands.entity(entity).insert(icon.clone());
+                }
+            } else if let Some(saved) = saved_cursors.remove(&entity) {
+                if window.cursor_options.visible != saved.visible {
+                    window.cursor_options.visible = saved.visible;
+                }
+                match saved.icon {
+                    Some(icon) => commands.entity(entity).insert(icon),
+                    None => commands.entity(entity).remove::<CursorIcon>(),
+                };
+            }
+        }
+    }
+
+    saved_cursors.retain(|entity, _| context_windows.contains(entity));
+}
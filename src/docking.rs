@@ -0,0 +1,19 @@
+// Docking support, enabled with the `docking` cargo feature
+
+use imgui::sys;
+
+/// Creates a dockspace that covers the whole window (or image target) of the given Ui, which
+/// ImGui windows can be docked into. Call this each frame before building any other windows,
+/// and enable docking with `ImguiPlugin::docking`.
+///
+/// The central node of the dockspace is transparent and passes input through, so the game
+/// remains visible and interactive around the docked windows. Returns the id of the dockspace.
+pub fn imgui_dockspace_over_window(_ui: &imgui::Ui) -> sys::ImGuiID {
+    unsafe {
+        sys::igDockSpaceOverViewport(
+            sys::igGetMainViewport(),
+            sys::ImGuiDockNodeFlags_PassthruCentralNode as sys::ImGuiDockNodeFlags,
+            std::ptr::null(),
+        )
+    }
+}
@@ -0,0 +1,366 @@
+// Configuration of the fonts in ImGui's font atlas
+
+use bevy::prelude::*;
+use imgui::{FontAtlas, FontConfig, FontGlyphRanges, FontId, FontSource};
+use std::{collections::BTreeSet, sync::Arc};
+
+/// A font to add to ImGui's font atlas. See `ImguiPlugin::fonts`.
+#[derive(Clone)]
+pub struct ImguiFont {
+    /// Where the font data comes from (default is `ImguiFontSource::Default`).
+    pub source: ImguiFontSource,
+
+    /// The unscaled font size in pixels (default is 13).
+    pub size: f32,
+
+    /// The glyphs to include in the atlas (default is `ImguiGlyphRanges::Default`).
+    pub glyph_ranges: ImguiGlyphRanges,
+
+    /// Whether to merge the glyphs of this font into the previous font, rather than adding a
+    /// separate font (default is false). This is typically used to add icons from an icon font
+    /// to a text font, in which case `glyph_ranges` should only cover the icons.
+    pub merge_mode: bool,
+
+    /// The unscaled minimum advance in pixels of each glyph, which can be used to make the
+    /// glyphs of an icon font monospaced (default is 0).
+    pub glyph_min_advance_x: f32,
+
+    /// The unscaled offset in pixels of each glyph, which can be used to align the glyphs of a
+    /// merged font with the font it is merged into (default is `[0.0, 0.0]`).
+    pub glyph_offset: [f32; 2],
+}
+
+impl ImguiFont {
+    /// Creates a font with default settings from the given source and unscaled size in pixels.
+    pub fn new(source: ImguiFontSource, size: f32) -> Self {
+        Self {
+            source,
+            size,
+            ..default()
+        }
+    }
+}
+
+impl Default for ImguiFont {
+    fn default() -> Self {
+        Self {
+            source: ImguiFontSource::Default,
+            size: 13.0,
+            glyph_ranges: ImguiGlyphRanges::Default,
+            merge_mode: false,
+            glyph_min_advance_x: 0.0,
+            glyph_offset: [0.0, 0.0],
+        }
+    }
+}
+
+/// Where the data of an `ImguiFont` comes from.
+#[derive(Clone)]
+pub enum ImguiFontSource {
+    /// Dear ImGui's built-in font.
+    Default,
+
+    /// TrueType or OpenType font data.
+    Data(Arc<Vec<u8>>),
+
+    /// A Bevy font asset. The font atlas is built once the asset has loaded, using the built-in
+    /// font until then. Requires the `bevy_text` cargo feature.
+    #[cfg(feature = "bevy_text")]
+    Asset(Handle<Font>),
+}
+
+/// The glyphs of a font to include in the atlas.
+///
+/// The Chinese and Japanese presets contain thousands of glyphs, which produces a large atlas
+/// texture. `Registered` can be used instead to only include the glyphs of text that the app
+/// registers with `ImguiContext::register_glyphs`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ImguiGlyphRanges {
+    /// Basic Latin and Latin Supplement.
+    #[default]
+    Default,
+
+    /// Basic Latin, Latin Supplement, Latin Extended-A and -B, and Latin Extended Additional.
+    LatinExtended,
+
+    /// Basic Latin, Latin Supplement, and Cyrillic.
+    Cyrillic,
+
+    /// Basic Latin, Latin Supplement, Hiragana, Katakana, and around 3000 common Kanji.
+    Japanese,
+
+    /// Basic Latin, Latin Supplement, Hiragana, Katakana, and around 2500 common simplified
+    /// Chinese ideograms.
+    ChineseSimplifiedCommon,
+
+    /// Basic Latin, Latin Supplement, Hiragana, Katakana, and all CJK Unified Ideographs.
+    ChineseFull,
+
+    /// Basic Latin, Latin Supplement, and Korean.
+    Korean,
+
+    /// Basic Latin, Latin Supplement, and Thai.
+    Thai,
+
+    /// Basic Latin, Latin Supplement, and Vietnamese.
+    Vietnamese,
+
+    /// Pairs of inclusive codepoint ranges, terminated by a zero (e.g. `&[0x20, 0xFF, 0]`).
+    Custom(&'static [u32]),
+
+    /// Basic Latin, Latin Supplement, and the characters of every string registered with
+    /// `ImguiContext::register_glyphs`. The font atlas is rebuilt whenever a registered string
+    /// contains new characters.
+    ///
+    /// Text passed to widgets is **not** collected, as Dear ImGui does not report the text it
+    /// draws. Any text outside Latin Supplement must be registered before it is drawn (e.g.
+    /// localised strings as they are loaded), or it is shown with the font's fallback glyph.
+    /// Only keyboard and IME text input is registered automatically.
+    Registered,
+}
+
+// Latin Extended-A and -B (0x0100 - 0x024F), and Latin Extended Additional (0x1E00 - 0x1EFF)
+const LATIN_EXTENDED_RANGES: &[u32] = &[0x0020, 0x024F, 0x1E00, 0x1EFF, 0];
+
+// The largest codepoint that Dear ImGui can render, as it is built with 16-bit characters
+const MAX_CODEPOINT: u32 = 0xFFFF;
+
+impl ImguiGlyphRanges {
+    // The ranges of a Registered font must outlive the font atlas build that uses them
+    fn to_imgui(&self, registered_ranges: &[u32]) -> FontGlyphRanges {
+        match self {
+            ImguiGlyphRanges::Default => FontGlyphRanges::default(),
+            ImguiGlyphRanges::LatinExtended => FontGlyphRanges::from_slice(LATIN_EXTENDED_RANGES),
+            ImguiGlyphRanges::Cyrillic => FontGlyphRanges::cyrillic(),
+            ImguiGlyphRanges::Japanese => FontGlyphRanges::japanese(),
+            ImguiGlyphRanges::ChineseSimplifiedCommon => {
+                FontGlyphRanges::chinese_simplified_common()
+            }
+            ImguiGlyphRanges::ChineseFull => FontGlyphRanges::chinese_full(),
+            ImguiGlyphRanges::Korean => FontGlyphRanges::korean(),
+            ImguiGlyphRanges::Thai => FontGlyphRanges::thai(),
+            ImguiGlyphRanges::Vietnamese => FontGlyphRanges::vietnamese(),
+            ImguiGlyphRanges::Custom(ranges) => FontGlyphRanges::from_slice(ranges),
+            ImguiGlyphRanges::Registered => unsafe {
+                FontGlyphRanges::from_ptr(registered_ranges.as_ptr())
+            },
+        }
+    }
+}
+
+/// The characters collected for fonts with `ImguiGlyphRanges::Registered`.
+#[derive(Default)]
+pub(crate) struct RegisteredGlyphs(BTreeSet<char>);
+
+impl RegisteredGlyphs {
+    // Adds the characters of the given text, returning whether any were new
+    pub(crate) fn add(&mut self, text: &str) -> bool {
+        let mut added = false;
+        for c in text.chars() {
+            if (0x100..=MAX_CODEPOINT).contains(&(c as u32)) {
+                added |= self.0.insert(c);
+            }
+        }
+        added
+    }
+
+    // Returns the glyph ranges that cover Basic Latin, Latin Supplement and every collected
+    // character, terminated by a zero
+    fn ranges(&self) -> Vec<u32> {
+        let mut ranges = vec![0x0020, 0x00FF];
+        for c in self.0.iter().map(|c| *c as u32) {
+            match ranges.last_mut() {
+                Some(end) if *end + 1 == c => *end = c,
+                _ => ranges.extend([c, c]),
+            }
+        }
+        ranges.push(0);
+        ranges
+    }
+}
+
+/// The configuration of ImGui's font atlas. This is initialised from the font settings of
+/// `ImguiPlugin`, and can be changed at runtime with `ImguiContext::request_font_rebuild`.
+#[derive(Clone)]
+pub struct ImguiFontAtlasConfig {
+    /// The fonts to add to the atlas, the first of which is the default font. If empty, ImGui's
+    /// built-in font is used at `font_size`.
+    pub fonts: Vec<ImguiFont>,
+
+    /// The unscaled font size to use for ImGui's built-in font when `fonts` is empty.
+    pub font_size: f32,
+
+    /// The number of horizontal font samples to perform. Must be >= 1.
+    pub font_oversample_h: i32,
+
+    /// The number of vertical font samples to perform. Must be >= 1.
+    pub font_oversample_v: i32,
+}
+
+impl ImguiFontAtlasConfig {
+    // Whether the atlas needs rebuilding when new glyphs are registered
+    pub(crate) fn uses_registered_glyphs(&self) -> bool {
+        self.fonts
+            .iter()
+            .any(|font| font.glyph_ranges == ImguiGlyphRanges::Registered)
+    }
+}
+
+/// Sent once the font atlas of every ImGui context has been rebuilt with new fonts, either
+/// because the configured font assets have loaded, or because of a call to
+/// `ImguiContext::request_font_rebuild`. The ids returned by `ImguiContext::font_id` refer to
+/// the new fonts from this point on.
+#[derive(Event, Clone, Debug)]
+pub struct ImguiFontAtlasRebuilt;
+
+// A font atlas configuration along with the data of each font, once every font asset has loaded
+pub(crate) struct ResolvedFonts {
+    fonts: Vec<(ImguiFont, Option<Arc<Vec<u8>>>)>,
+    registered_ranges: Vec<u32>,
+    oversample_h: i32,
+    oversample_v: i32,
+}
+
+impl ResolvedFonts {
+    // Resolves the data of the configured fonts, returning None if any font asset has not
+    // loaded yet
+    #[cfg_attr(not(feature = "bevy_text"), allow(unused_variables))]
+    pub(crate) fn resolve(
+        config: &ImguiFontAtlasConfig,
+        registered_glyphs: &RegisteredGlyphs,
+        world: &World,
+    ) -> Option<Self> {
+        if config.fonts.is_empty() {
+            return Some(Self::fallback(config));
+        }
+
+        let mut fonts = Vec::with_capacity(config.fonts.len());
+        for font in &config.fonts {
+            let data = match &font.source {
+                ImguiFontSource::Default => None,
+                ImguiFontSource::Data(data) => Some(data.clone()),
+                #[cfg(feature = "bevy_text")]
+                ImguiFontSource::Asset(handle) => {
+                    let font_assets = world.get_resource::<Assets<bevy::text::Font>>()?;
+                    Some(font_assets.get(handle)?.data.clone())
+                }
+            };
+            fonts.push((font.clone(), data));
+        }
+        Some(Self {
+            fonts,
+            registered_ranges: registered_glyphs.ranges(),
+            oversample_h: config.font_oversample_h,
+            oversample_v: config.font_oversample_v,
+        })
+    }
+
+    // The built-in font at the configured size, for use when no fonts are configured, or until
+    // the configured fonts have loaded
+    pub(crate) fn fallback(config: &ImguiFontAtlasConfig) -> Self {
+        Self {
+            fonts: vec![(
+                ImguiFont {
+                    size: config.font_size,
+                    ..default()
+                },
+                None,
+            )],
+            registered_ranges: Vec::new(),
+            oversample_h: config.font_oversample_h,
+            oversample_v: config.font_oversample_v,
+        }
+    }
+
+    // Adds the fonts to the atlas, scaling their size and oversampling. Returns the id of each
+    // font, where fonts that are merged into a previous font have the id of that font.
+    pub(crate) fn add_to_atlas(
+        &self,
+        atlas: &mut FontAtlas,
+        font_scale: f32,
+        oversample_scale: i32,
+    ) -> Vec<FontId> {
+        let mut font_ids = Vec::new();
+        let mut index = 0;
+        while index < self.fonts.len() {
+            // Fonts are added along with any following fonts that are merged into them
+            let end = self.fonts[index + 1..]
+                .iter()
+                .position(|(font, _)| !font.merge_mode)
+                .map_or(self.fonts.len(), |offset| index + 1 + offset);
+
+            let sources: Vec<_> = self.fonts[index..end]
+                .iter()
+                .map(|(font, data)| {
+                    let config = FontConfig {
+                        // Round down to nearest integer, as per https://github.com/ocornut/imgui/blob/master/docs/FAQ.md#q-how-should-i-handle-dpi-in-my-application
+                        size_pixels: f32::floor(font.size * font_scale),
+                        oversample_h: self.oversample_h * oversample_scale,
+                        oversample_v: self.oversample_v * oversample_scale,
+                        glyph_ranges: font.glyph_ranges.to_imgui(&self.registered_ranges),
+                        glyph_min_advance_x: font.glyph_min_advance_x * font_scale,
+                        glyph_offset: font.glyph_offset.map(|offset| offset * font_scale),
+                        ..default()
+                    };
+                    match data {
+                        Some(data) => FontSource::TtfData {
+                            data,
+                            size_pixels: config.size_pixels,
+                            config: Some(config),
+                        },
+                        None => FontSource::DefaultFontData {
+                            config: Some(config),
+                        },
+                    }
+                })
+                .collect();
+
+            let font_id = atlas.add_font(&sources);
+            font_ids.resize(end, font_id);
+            index = end;
+        }
+        font_ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registered_glyph_ranges_include_latin_1() {
+        let glyphs = RegisteredGlyphs::default();
+        assert_eq!(glyphs.ranges(), [0x0020, 0x00FF, 0]);
+    }
+
+    #[test]
+    fn registered_glyph_ranges_merge_consecutive_characters() {
+        let mut glyphs = RegisteredGlyphs::default();
+        assert!(glyphs.add("дгвб"));
+        assert!(glyphs.add("ая"));
+        assert_eq!(
+            glyphs.ranges(),
+            [0x0020, 0x00FF, 0x0430, 0x0434, 0x044F, 0x044F, 0]
+        );
+    }
+
+    #[test]
+    fn registered_glyph_ranges_extend_latin_1() {
+        let mut glyphs = RegisteredGlyphs::default();
+        assert!(glyphs.add("\u{100}\u{101}"));
+        assert_eq!(glyphs.ranges(), [0x0020, 0x0101, 0]);
+    }
+
+    #[test]
+    fn registered_glyphs_ignore_known_and_unrenderable_characters() {
+        let mut glyphs = RegisteredGlyphs::default();
+        assert!(!glyphs.add("Latin-1 only: é"));
+        assert!(!glyphs.add("\u{1F600}"));
+        assert!(glyphs.add("日本"));
+        assert!(!glyphs.add("日本"));
+        assert_eq!(
+            glyphs.ranges(),
+            [0x0020, 0x00FF, 0x65E5, 0x65E5, 0x672C, 0x672C, 0]
+        );
+    }
+}
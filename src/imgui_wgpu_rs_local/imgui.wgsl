@@ -22,6 +22,7 @@ SOFTWARE.
 
 struct Uniforms {
     u_Matrix: mat4x4<f32>,
+    u_ColorScale: f32,
 };
 
 struct VertexInput {
@@ -68,9 +69,9 @@ fn srgb_to_linear(srgb: vec4<f32>) -> vec4<f32> {
 
 @fragment
 fn fs_main_linear(in: VertexOutput) -> FragmentOutput {
-    let color = srgb_to_linear(in.v_Color);
+    let color = srgb_to_linear(in.v_Color) * textureSample(u_Texture, u_Sampler, in.v_UV);
 
-    return FragmentOutput(color * textureSample(u_Texture, u_Sampler, in.v_UV));
+    return FragmentOutput(vec4<f32>(color.rgb * uniforms.u_ColorScale, color.a));
 }
 
 @fragment
@@ -78,4 +79,23 @@ fn fs_main_srgb(in: VertexOutput) -> FragmentOutput {
     let color = in.v_Color;
 
     return FragmentOutput(color * textureSample(u_Texture, u_Sampler, in.v_UV));
-}
\ No newline at end of file
+}
+
+// A single channel (Alpha8) font atlas stores coverage in the red channel of white glyphs
+fn sample_alpha8(uv: vec2<f32>) -> vec4<f32> {
+    return vec4<f32>(1.0, 1.0, 1.0, textureSample(u_Texture, u_Sampler, uv).r);
+}
+
+@fragment
+fn fs_main_linear_alpha8(in: VertexOutput) -> FragmentOutput {
+    let color = srgb_to_linear(in.v_Color) * sample_alpha8(in.v_UV);
+
+    return FragmentOutput(vec4<f32>(color.rgb * uniforms.u_ColorScale, color.a));
+}
+
+@fragment
+fn fs_main_srgb_alpha8(in: VertexOutput) -> FragmentOutput {
+    let color = in.v_Color;
+
+    return FragmentOutput(color * sample_alpha8(in.v_UV));
+}
@@ -23,10 +23,13 @@ SOFTWARE.
 #![allow(dead_code)]
 
 use imgui::{
-    Context, DrawCmd::Elements, DrawData, DrawIdx, DrawList, DrawVert, TextureId, Textures,
+    Context,
+    DrawCmd::{Elements, RawCallback, ResetRenderState},
+    DrawData, DrawIdx, DrawList, DrawVert, TextureId, Textures,
 };
 use smallvec::SmallVec;
 use std::error::Error;
+use std::ffi::c_void;
 use std::fmt;
 use std::mem::size_of;
 use std::sync::Arc;
@@ -36,6 +39,12 @@ use wgpu::*;
 static VS_ENTRY_POINT: &str = "vs_main";
 static FS_ENTRY_POINT_LINEAR: &str = "fs_main_linear";
 static FS_ENTRY_POINT_SRGB: &str = "fs_main_srgb";
+static FS_ENTRY_POINT_LINEAR_ALPHA8: &str = "fs_main_linear_alpha8";
+static FS_ENTRY_POINT_SRGB_ALPHA8: &str = "fs_main_srgb_alpha8";
+
+/// The brightness in nits of a linear colour value of 1.0 in the scRGB colour space used by
+/// floating-point HDR surfaces.
+pub const SCRGB_REFERENCE_WHITE_NITS: f32 = 80.0;
 
 pub type RendererResult<T> = Result<T, RendererError>;
 
@@ -64,6 +73,19 @@ impl fmt::Display for RendererError {
 
 impl Error for RendererError {}
 
+/// A `DrawCmd::RawCallback` command encountered while rendering a draw list.
+pub struct DrawCallback {
+    /// The callback function that was added to the draw list.
+    pub callback: unsafe extern "C" fn(*const imgui::sys::ImDrawList, *const imgui::sys::ImDrawCmd),
+    /// The user data that was added to the draw list along with the callback.
+    pub user_data: *mut c_void,
+    /// The clip rectangle at this point in the draw list, in framebuffer pixels
+    /// (min x, min y, max x, max y). The scissor rect of the render pass is set to this rectangle.
+    pub clip_rect: [f32; 4],
+    /// The size of the framebuffer in pixels.
+    pub fb_size: [f32; 2],
+}
+
 #[allow(dead_code)]
 enum ShaderStage {
     Vertex,
@@ -237,7 +259,7 @@ impl Texture {
 
     /// Write `data` to the texture.
     ///
-    /// - `data`: 32-bit RGBA bitmap data.
+    /// - `data`: bitmap data in the format of the texture (e.g. 32-bit RGBA).
     /// - `width`: The width of the source bitmap (`data`) in pixels.
     /// - `height`: The height of the source bitmap (`data`) in pixels.
     pub fn write(&self, queue: &Queue, data: &[u8], width: u32, height: u32) {
@@ -254,7 +276,9 @@ impl Texture {
             // layout of the source bitmap
             TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(width * 4),
+                bytes_per_row: Some(
+                    width * self.texture.format().block_copy_size(None).unwrap_or(4),
+                ),
                 rows_per_image: Some(height),
             },
             // size of the source bitmap
@@ -305,6 +329,13 @@ pub struct RendererConfig<'s> {
     pub shader: Option<ShaderModuleDescriptor<'s>>,
     pub vertex_shader_entry_point: Option<&'s str>,
     pub fragment_shader_entry_point: Option<&'s str>,
+    /// Scales the colour output of the linear fragment shader. On HDR targets, this maps
+    /// ImGui's white to the desired paper-white brightness (default is 1.0).
+    pub color_scale: f32,
+    /// When set, the font atlas is uploaded as a single channel `R8Unorm` texture, and drawn
+    /// with this fragment shader entry point, which must expand the red channel into alpha
+    /// (default is None).
+    pub font_fragment_shader_entry_point: Option<&'s str>,
 }
 
 impl<'s> RendererConfig<'s> {
@@ -317,6 +348,8 @@ impl<'s> RendererConfig<'s> {
             shader: Some(shader),
             vertex_shader_entry_point: Some(VS_ENTRY_POINT),
             fragment_shader_entry_point: Some(FS_ENTRY_POINT_LINEAR),
+            color_scale: 1.0,
+            font_fragment_shader_entry_point: None,
         }
     }
 }
@@ -350,6 +383,32 @@ impl RendererConfig<'_> {
             ..Self::with_shaders(include_wgsl!("imgui.wgsl"))
         }
     }
+
+    /// Create a new renderer config with precompiled default shaders outputting linear color,
+    /// scaled so that white is displayed at `paper_white_nits`.
+    ///
+    /// If you write to an Rgba16Float scRGB framebuffer, this is what you want.
+    pub fn new_hdr(paper_white_nits: f32) -> Self {
+        RendererConfig {
+            color_scale: paper_white_nits / SCRGB_REFERENCE_WHITE_NITS,
+            ..Self::new()
+        }
+    }
+
+    /// Uploads the font atlas as a single channel texture, which uses a quarter of the memory
+    /// of an RGBA texture. This is only supported by the precompiled default shaders.
+    pub fn with_alpha8_font_atlas(self) -> Self {
+        let font_fragment_shader_entry_point =
+            if self.fragment_shader_entry_point == Some(FS_ENTRY_POINT_SRGB) {
+                FS_ENTRY_POINT_SRGB_ALPHA8
+            } else {
+                FS_ENTRY_POINT_LINEAR_ALPHA8
+            };
+        RendererConfig {
+            font_fragment_shader_entry_point: Some(font_fragment_shader_entry_point),
+            ..self
+        }
+    }
 }
 
 pub struct RenderData {
@@ -366,6 +425,9 @@ pub struct RenderData {
 
 pub struct Renderer {
     pipeline: RenderPipeline,
+    /// The pipeline used to draw with a single channel font atlas, if enabled.
+    font_pipeline: Option<RenderPipeline>,
+    font_texture_id: TextureId,
     uniform_buffer: Buffer,
     uniform_bind_group: BindGroup,
     /// Textures of the font atlas and all images.
@@ -390,13 +452,15 @@ impl Renderer {
             shader,
             vertex_shader_entry_point,
             fragment_shader_entry_point,
+            color_scale,
+            font_fragment_shader_entry_point,
         } = config;
 
         // Load shaders.
         let shader_module = device.create_shader_module(shader.unwrap());
 
-        // Create the uniform matrix buffer.
-        let size = 64;
+        // Create the uniform buffer, containing the matrix and the color scale (padded to 16 bytes).
+        let size = 80;
         let uniform_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("imgui-wgpu uniform buffer"),
             size,
@@ -404,12 +468,18 @@ impl Renderer {
             mapped_at_creation: false,
         });
 
+        queue.write_buffer(
+            &uniform_buffer,
+            64,
+            bytemuck::bytes_of(&[color_scale, 0.0, 0.0, 0.0]),
+        );
+
         // Create the uniform matrix buffer bind group layout.
         let uniform_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: None,
             entries: &[BindGroupLayoutEntry {
                 binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
                 ty: BindingType::Buffer {
                     ty: BufferBindingType::Uniform,
                     has_dynamic_offset: false,
@@ -460,67 +530,78 @@ impl Renderer {
         });
 
         // Create the render pipeline.
-        // Create the render pipeline.
-        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("imgui-wgpu pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: VertexState {
-                module: &shader_module,
-                entry_point: vertex_shader_entry_point,
-                compilation_options: Default::default(),
-                buffers: &[VertexBufferLayout {
-                    array_stride: size_of::<DrawVert>() as BufferAddress,
-                    step_mode: VertexStepMode::Vertex,
-                    attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Unorm8x4],
-                }],
-            },
-            primitive: PrimitiveState {
-                topology: PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: FrontFace::Cw,
-                cull_mode: None,
-                polygon_mode: PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
-                format,
-                depth_write_enabled: false,
-                depth_compare: wgpu::CompareFunction::Always,
-                stencil: wgpu::StencilState::default(),
-                bias: DepthBiasState::default(),
-            }),
-            multisample: MultisampleState {
-                count: sample_count,
-                ..Default::default()
-            },
-            fragment: Some(FragmentState {
-                module: &shader_module,
-                entry_point: fragment_shader_entry_point,
-                compilation_options: Default::default(),
-                targets: &[Some(ColorTargetState {
-                    format: texture_format,
-                    blend: Some(BlendState {
-                        color: BlendComponent {
-                            src_factor: BlendFactor::SrcAlpha,
-                            dst_factor: BlendFactor::OneMinusSrcAlpha,
-                            operation: BlendOperation::Add,
-                        },
-                        alpha: BlendComponent {
-                            src_factor: BlendFactor::OneMinusDstAlpha,
-                            dst_factor: BlendFactor::One,
-                            operation: BlendOperation::Add,
-                        },
-                    }),
-                    write_mask: ColorWrites::ALL,
-                })],
-            }),
-            multiview: None,
-            cache: None,
-        });
+        let create_pipeline = |label: &str, fragment_shader_entry_point: Option<&str>| {
+            device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader_module,
+                    entry_point: vertex_shader_entry_point,
+                    compilation_options: Default::default(),
+                    buffers: &[VertexBufferLayout {
+                        array_stride: size_of::<DrawVert>() as BufferAddress,
+                        step_mode: VertexStepMode::Vertex,
+                        attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Unorm8x4],
+                    }],
+                },
+                primitive: PrimitiveState {
+                    topology: PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: FrontFace::Cw,
+                    cull_mode: None,
+                    polygon_mode: PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
+                    format,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState::default(),
+                    bias: DepthBiasState::default(),
+                }),
+                multisample: MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                fragment: Some(FragmentState {
+                    module: &shader_module,
+                    entry_point: fragment_shader_entry_point,
+                    compilation_options: Default::default(),
+                    targets: &[Some(ColorTargetState {
+                        format: texture_format,
+                        blend: Some(BlendState {
+                            color: BlendComponent {
+                                src_factor: BlendFactor::SrcAlpha,
+                                dst_factor: BlendFactor::OneMinusSrcAlpha,
+                                operation: BlendOperation::Add,
+                            },
+                            alpha: BlendComponent {
+                                src_factor: BlendFactor::OneMinusDstAlpha,
+                                dst_factor: BlendFactor::One,
+                                operation: BlendOperation::Add,
+                            },
+                        }),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let pipeline = create_pipeline("imgui-wgpu pipeline", fragment_shader_entry_point);
+        let font_pipeline =
+            font_fragment_shader_entry_point.map(|font_fragment_shader_entry_point| {
+                create_pipeline(
+                    "imgui-wgpu font pipeline",
+                    Some(font_fragment_shader_entry_point),
+                )
+            });
 
         let mut renderer = Self {
             pipeline,
+            font_pipeline,
+            font_texture_id: imgui.fonts().tex_id,
             uniform_buffer,
             uniform_bind_group,
             textures: Textures::new(),
@@ -533,6 +614,8 @@ impl Renderer {
                 shader: None,
                 vertex_shader_entry_point: None,
                 fragment_shader_entry_point: None,
+                color_scale,
+                font_fragment_shader_entry_point: None,
             },
         };
 
@@ -669,11 +752,15 @@ impl Renderer {
     /// Render the current imgui frame.  `Renderer::prepare` must be
     /// called first, and the output render data must be kept for the
     /// lifetime of the renderpass.
+    ///
+    /// Any `DrawCmd::RawCallback` commands in the draw lists are passed to
+    /// `callback_handler`, along with the render pass.
     pub fn split_render<'r>(
         &'r self,
         draw_data: &DrawData,
         render_data: &'r RenderData,
         rpass: &mut RenderPass<'r>,
+        callback_handler: &mut dyn FnMut(&mut RenderPass<'_>, &DrawCallback),
     ) -> RendererResult<()> {
         if !render_data.render {
             return Ok(());
@@ -689,10 +776,7 @@ impl Renderer {
             return Ok(());
         }
 
-        rpass.set_pipeline(&self.pipeline);
-        rpass.set_bind_group(0, &self.uniform_bind_group, &[]);
-        rpass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        rpass.set_index_buffer(index_buffer.slice(..), IndexFormat::Uint16);
+        self.setup_render_state(render_data, rpass);
 
         // Execute all the imgui render work.
         for (draw_list, bases) in draw_data
@@ -702,10 +786,11 @@ impl Renderer {
             self.render_draw_list(
                 rpass,
                 draw_list,
-                render_data.fb_size,
+                render_data,
                 draw_data.display_pos,
                 draw_data.framebuffer_scale,
                 *bases,
+                callback_handler,
             )?;
         }
 
@@ -713,70 +798,94 @@ impl Renderer {
     }
 
     /// Render the current imgui frame.
+    ///
+    /// Any `DrawCmd::RawCallback` commands in the draw lists are passed to
+    /// `callback_handler`, along with the render pass.
     pub fn render<'r>(
         &'r mut self,
         draw_data: &DrawData,
         queue: &Queue,
         device: &Device,
         rpass: &mut RenderPass<'r>,
+        callback_handler: &mut dyn FnMut(&mut RenderPass<'_>, &DrawCallback),
     ) -> RendererResult<()> {
         let render_data = self.render_data.take();
         self.render_data = Some(self.prepare(draw_data, render_data, queue, device));
-        self.split_render(draw_data, self.render_data.as_ref().unwrap(), rpass)
+        self.split_render(
+            draw_data,
+            self.render_data.as_ref().unwrap(),
+            rpass,
+            callback_handler,
+        )
+    }
+
+    /// Binds the imgui pipeline, uniforms and buffers on the renderpass, and
+    /// resets the viewport to cover the whole framebuffer.
+    fn setup_render_state(&self, render_data: &RenderData, rpass: &mut RenderPass<'_>) {
+        let vertex_buffer = render_data.vertex_buffer.as_ref().unwrap();
+        let index_buffer = render_data.index_buffer.as_ref().unwrap();
+
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        rpass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        rpass.set_index_buffer(index_buffer.slice(..), IndexFormat::Uint16);
+        rpass.set_viewport(
+            0.0,
+            0.0,
+            render_data.fb_size[0],
+            render_data.fb_size[1],
+            0.0,
+            1.0,
+        );
     }
 
     /// Render a given `DrawList` from imgui onto a wgpu frame.
+    #[allow(clippy::too_many_arguments)]
     fn render_draw_list<'render>(
         &'render self,
         rpass: &mut RenderPass<'render>,
         draw_list: &DrawList,
-        fb_size: [f32; 2],
+        render_data: &RenderData,
         clip_off: [f32; 2],
         clip_scale: [f32; 2],
         (vertex_base, index_base): (i32, u32),
+        callback_handler: &mut dyn FnMut(&mut RenderPass<'_>, &DrawCallback),
     ) -> RendererResult<()> {
+        let fb_size = render_data.fb_size;
+        let to_fb_clip_rect = |clip_rect: [f32; 4]| {
+            [
+                (clip_rect[0] - clip_off[0]) * clip_scale[0],
+                (clip_rect[1] - clip_off[1]) * clip_scale[1],
+                (clip_rect[2] - clip_off[0]) * clip_scale[0],
+                (clip_rect[3] - clip_off[1]) * clip_scale[1],
+            ]
+        };
+
         for cmd in draw_list.commands() {
-            if let Elements { count, cmd_params } = cmd {
-                let clip_rect = [
-                    (cmd_params.clip_rect[0] - clip_off[0]) * clip_scale[0],
-                    (cmd_params.clip_rect[1] - clip_off[1]) * clip_scale[1],
-                    (cmd_params.clip_rect[2] - clip_off[0]) * clip_scale[0],
-                    (cmd_params.clip_rect[3] - clip_off[1]) * clip_scale[1],
-                ];
-
-                // Set the current texture bind group on the renderpass.
-                let texture_id = cmd_params.texture_id;
-                let tex = self
-                    .textures
-                    .get(texture_id)
-                    .ok_or(RendererError::BadTexture(texture_id))?;
-                rpass.set_bind_group(1, Some(tex.bind_group.as_ref()), &[]);
-
-                // Set scissors on the renderpass.
-                let start = index_base + cmd_params.idx_offset as u32;
-                let end = start + count as u32;
-                if clip_rect[0] < fb_size[0]
-                    && clip_rect[1] < fb_size[1]
-                    && clip_rect[2] >= 0.0
-                    && clip_rect[3] >= 0.0
-                {
-                    let scissors = (
-                        clip_rect[0].max(0.0).floor() as u32,
-                        clip_rect[1].max(0.0).floor() as u32,
-                        (clip_rect[2].min(fb_size[0]) - clip_rect[0].max(0.0))
-                            .abs()
-                            .ceil() as u32,
-                        (clip_rect[3].min(fb_size[1]) - clip_rect[1].max(0.0))
-                            .abs()
-                            .ceil() as u32,
-                    );
-
-                    // XXX: Work-around for wgpu issue [1] by only issuing draw
-                    // calls if the scissor rect is valid (by wgpu's flawed
-                    // logic). Regardless, a zero-width or zero-height scissor
-                    // is essentially a no-op render anyway, so just skip it.
-                    // [1]: https://github.com/gfx-rs/wgpu/issues/1750
-                    if scissors.2 > 0 && scissors.3 > 0 {
+            match cmd {
+                Elements { count, cmd_params } => {
+                    let clip_rect = to_fb_clip_rect(cmd_params.clip_rect);
+
+                    // Set the current texture bind group on the renderpass.
+                    let texture_id = cmd_params.texture_id;
+                    if let Some(font_pipeline) = &self.font_pipeline {
+                        // A single channel font atlas needs its own fragment shader
+                        if texture_id == self.font_texture_id {
+                            rpass.set_pipeline(font_pipeline);
+                        } else {
+                            rpass.set_pipeline(&self.pipeline);
+                        }
+                    }
+                    let tex = self
+                        .textures
+                        .get(texture_id)
+                        .ok_or(RendererError::BadTexture(texture_id))?;
+                    rpass.set_bind_group(1, Some(tex.bind_group.as_ref()), &[]);
+
+                    // Set scissors on the renderpass.
+                    let start = index_base + cmd_params.idx_offset as u32;
+                    let end = start + count as u32;
+                    if let Some(scissors) = Self::scissor_rect(clip_rect, fb_size) {
                         rpass.set_scissor_rect(scissors.0, scissors.1, scissors.2, scissors.3);
 
                         // Draw the current batch of vertices with the renderpass.
@@ -787,11 +896,68 @@ impl Renderer {
                         );
                     }
                 }
+                ResetRenderState => {
+                    self.setup_render_state(render_data, rpass);
+                }
+                RawCallback { callback, raw_cmd } => {
+                    // Safety: imgui guarantees that the command is valid for the lifetime of the draw list.
+                    let raw_cmd = unsafe { &*raw_cmd };
+                    let clip_rect = to_fb_clip_rect([
+                        raw_cmd.ClipRect.x,
+                        raw_cmd.ClipRect.y,
+                        raw_cmd.ClipRect.z,
+                        raw_cmd.ClipRect.w,
+                    ]);
+
+                    // Callbacks that would be entirely clipped are skipped.
+                    if let Some(scissors) = Self::scissor_rect(clip_rect, fb_size) {
+                        rpass.set_scissor_rect(scissors.0, scissors.1, scissors.2, scissors.3);
+                        callback_handler(
+                            rpass,
+                            &DrawCallback {
+                                callback,
+                                user_data: raw_cmd.UserCallbackData,
+                                clip_rect,
+                                fb_size,
+                            },
+                        );
+                    }
+                }
             }
         }
         Ok(())
     }
 
+    /// Converts a clip rect in framebuffer pixels to a scissor rect (x, y, width, height),
+    /// or None if the clip rect does not cover any part of the framebuffer.
+    fn scissor_rect(clip_rect: [f32; 4], fb_size: [f32; 2]) -> Option<(u32, u32, u32, u32)> {
+        if clip_rect[0] >= fb_size[0]
+            || clip_rect[1] >= fb_size[1]
+            || clip_rect[2] < 0.0
+            || clip_rect[3] < 0.0
+        {
+            return None;
+        }
+
+        let scissors = (
+            clip_rect[0].max(0.0).floor() as u32,
+            clip_rect[1].max(0.0).floor() as u32,
+            (clip_rect[2].min(fb_size[0]) - clip_rect[0].max(0.0))
+                .abs()
+                .ceil() as u32,
+            (clip_rect[3].min(fb_size[1]) - clip_rect[1].max(0.0))
+                .abs()
+                .ceil() as u32,
+        );
+
+        // XXX: Work-around for wgpu issue [1] by only issuing draw
+        // calls if the scissor rect is valid (by wgpu's flawed
+        // logic). Regardless, a zero-width or zero-height scissor
+        // is essentially a no-op render anyway, so just skip it.
+        // [1]: https://github.com/gfx-rs/wgpu/issues/1750
+        (scissors.2 > 0 && scissors.3 > 0).then_some(scissors)
+    }
+
     /// Updates the current uniform buffer containing the transform matrix.
     fn update_uniform_buffer(&self, queue: &Queue, matrix: &[[f32; 4]; 4]) {
         let data = bytemuck::bytes_of(matrix);
@@ -803,13 +969,17 @@ impl Renderer {
     /// This has to be called after loading a font.
     pub fn reload_font_texture(&mut self, imgui: &mut Context, device: &Device, queue: &Queue) {
         let fonts = imgui.fonts();
-        // Remove possible font atlas texture.
-        self.textures.remove(fonts.tex_id);
 
-        // Create font texture and upload it.
-        let handle = fonts.build_rgba32_texture();
+        // Create font texture and upload it. The atlas is always 8-bit RGBA or alpha data,
+        // regardless of the format of the framebuffer.
+        let (handle, format) = if self.font_pipeline.is_some() {
+            (fonts.build_alpha8_texture(), TextureFormat::R8Unorm)
+        } else {
+            (fonts.build_rgba32_texture(), TextureFormat::Rgba8Unorm)
+        };
         let font_texture_cnfig = TextureConfig {
             label: Some("imgui-wgpu font atlas"),
+            format: Some(format),
             size: Extent3d {
                 width: handle.width,
                 height: handle.height,
@@ -820,7 +990,10 @@ impl Renderer {
 
         let font_texture = Texture::new(device, self, font_texture_cnfig);
         font_texture.write(queue, handle.data, handle.width, handle.height);
-        fonts.tex_id = self.textures.insert(font_texture);
+        // Replace any previous font atlas texture in place, so that the font texture id
+        // remains stable across reloads (and across renderers sharing the same context).
+        self.textures.replace(fonts.tex_id, font_texture);
+        self.font_texture_id = fonts.tex_id;
         // Clear imgui texture data to save memory.
         fonts.clear_tex_data();
     }
@@ -0,0 +1,597 @@
+// Translation of Bevy input into Dear ImGui input events
+
+use bevy::{
+    ecs::entity::Entity,
+    input::{
+        gamepad::{Gamepad, GamepadAxis, GamepadButton},
+        keyboard::{KeyCode, KeyboardInput},
+        mouse::{MouseButton, MouseButtonInput, MouseScrollUnit, MouseWheel},
+        touch::{TouchInput, TouchPhase},
+        ButtonState,
+    },
+    math::Vec2,
+};
+use imgui::Key;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use crate::ImguiTouch;
+
+// Maps a Bevy key code to the corresponding ImGui key, if there is one
+fn imgui_key(key_code: KeyCode) -> Option<Key> {
+    let key = match key_code {
+        KeyCode::Tab => Key::Tab,
+        KeyCode::ArrowLeft => Key::LeftArrow,
+        KeyCode::ArrowRight => Key::RightArrow,
+        KeyCode::ArrowUp => Key::UpArrow,
+        KeyCode::ArrowDown => Key::DownArrow,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Space => Key::Space,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Escape => Key::Escape,
+        KeyCode::ControlLeft => Key::LeftCtrl,
+        KeyCode::ShiftLeft => Key::LeftShift,
+        KeyCode::AltLeft => Key::LeftAlt,
+        KeyCode::SuperLeft => Key::LeftSuper,
+        KeyCode::ControlRight => Key::RightCtrl,
+        KeyCode::ShiftRight => Key::RightShift,
+        KeyCode::AltRight => Key::RightAlt,
+        KeyCode::SuperRight => Key::RightSuper,
+        KeyCode::ContextMenu => Key::Menu,
+        KeyCode::Digit0 => Key::Alpha0,
+        KeyCode::Digit1 => Key::Alpha1,
+        KeyCode::Digit2 => Key::Alpha2,
+        KeyCode::Digit3 => Key::Alpha3,
+        KeyCode::Digit4 => Key::Alpha4,
+        KeyCode::Digit5 => Key::Alpha5,
+        KeyCode::Digit6 => Key::Alpha6,
+        KeyCode::Digit7 => Key::Alpha7,
+        KeyCode::Digit8 => Key::Alpha8,
+        KeyCode::Digit9 => Key::Alpha9,
+        KeyCode::KeyA => Key::A,
+        KeyCode::KeyB => Key::B,
+        KeyCode::KeyC => Key::C,
+        KeyCode::KeyD => Key::D,
+        KeyCode::KeyE => Key::E,
+        KeyCode::KeyF => Key::F,
+        KeyCode::KeyG => Key::G,
+        KeyCode::KeyH => Key::H,
+        KeyCode::KeyI => Key::I,
+        KeyCode::KeyJ => Key::J,
+        KeyCode::KeyK => Key::K,
+        KeyCode::KeyL => Key::L,
+        KeyCode::KeyM => Key::M,
+        KeyCode::KeyN => Key::N,
+        KeyCode::KeyO => Key::O,
+        KeyCode::KeyP => Key::P,
+        KeyCode::KeyQ => Key::Q,
+        KeyCode::KeyR => Key::R,
+        KeyCode::KeyS => Key::S,
+        KeyCode::KeyT => Key::T,
+        KeyCode::KeyU => Key::U,
+        KeyCode::KeyV => Key::V,
+        KeyCode::KeyW => Key::W,
+        KeyCode::KeyX => Key::X,
+        KeyCode::KeyY => Key::Y,
+        KeyCode::KeyZ => Key::Z,
+        KeyCode::F1 => Key::F1,
+        KeyCode::F2 => Key::F2,
+        KeyCode::F3 => Key::F3,
+        KeyCode::F4 => Key::F4,
+        KeyCode::F5 => Key::F5,
+        KeyCode::F6 => Key::F6,
+        KeyCode::F7 => Key::F7,
+        KeyCode::F8 => Key::F8,
+        KeyCode::F9 => Key::F9,
+        KeyCode::F10 => Key::F10,
+        KeyCode::F11 => Key::F11,
+        KeyCode::F12 => Key::F12,
+        KeyCode::Quote => Key::Apostrophe,
+        KeyCode::Comma => Key::Comma,
+        KeyCode::Minus => Key::Minus,
+        KeyCode::Period => Key::Period,
+        KeyCode::Slash => Key::Slash,
+        KeyCode::Semicolon => Key::Semicolon,
+        KeyCode::Equal => Key::Equal,
+        KeyCode::BracketLeft => Key::LeftBracket,
+        KeyCode::Backslash => Key::Backslash,
+        KeyCode::BracketRight => Key::RightBracket,
+        KeyCode::Backquote => Key::GraveAccent,
+        KeyCode::CapsLock => Key::CapsLock,
+        KeyCode::ScrollLock => Key::ScrollLock,
+        KeyCode::NumLock => Key::NumLock,
+        KeyCode::PrintScreen => Key::PrintScreen,
+        KeyCode::Pause => Key::Pause,
+        KeyCode::Numpad0 => Key::Keypad0,
+        KeyCode::Numpad1 => Key::Keypad1,
+        KeyCode::Numpad2 => Key::Keypad2,
+        KeyCode::Numpad3 => Key::Keypad3,
+        KeyCode::Numpad4 => Key::Keypad4,
+        KeyCode::Numpad5 => Key::Keypad5,
+        KeyCode::Numpad6 => Key::Keypad6,
+        KeyCode::Numpad7 => Key::Keypad7,
+        KeyCode::Numpad8 => Key::Keypad8,
+        KeyCode::Numpad9 => Key::Keypad9,
+        KeyCode::NumpadDecimal => Key::KeypadDecimal,
+        KeyCode::NumpadDivide => Key::KeypadDivide,
+        KeyCode::NumpadMultiply => Key::KeypadMultiply,
+        KeyCode::NumpadSubtract => Key::KeypadSubtract,
+        KeyCode::NumpadAdd => Key::KeypadAdd,
+        KeyCode::NumpadEnter => Key::KeypadEnter,
+        KeyCode::NumpadEqual => Key::KeypadEqual,
+        _ => return None,
+    };
+    Some(key)
+}
+
+// Maps a Bevy modifier key code to the corresponding ImGui modifier key, along with the key
+// codes that together determine whether the modifier is held
+fn imgui_modifier(key_code: KeyCode) -> Option<(Key, [KeyCode; 2])> {
+    match key_code {
+        KeyCode::ControlLeft | KeyCode::ControlRight => {
+            Some((Key::ModCtrl, [KeyCode::ControlLeft, KeyCode::ControlRight]))
+        }
+        KeyCode::ShiftLeft | KeyCode::ShiftRight => {
+            Some((Key::ModShift, [KeyCode::ShiftLeft, KeyCode::ShiftRight]))
+        }
+        KeyCode::AltLeft | KeyCode::AltRight => {
+            Some((Key::ModAlt, [KeyCode::AltLeft, KeyCode::AltRight]))
+        }
+        KeyCode::SuperLeft | KeyCode::SuperRight => {
+            Some((Key::ModSuper, [KeyCode::SuperLeft, KeyCode::SuperRight]))
+        }
+        _ => None,
+    }
+}
+
+// Queues a Bevy keyboard event as ImGui key events, including any modifier key change. The held
+// keys are tracked from the events themselves rather than `ButtonInput<KeyCode>`, which is reset
+// while ImGui captures the keyboard if `ImguiPlugin::clear_captured_input` is set.
+pub(crate) fn add_keyboard_event(
+    io: &mut imgui::Io,
+    event: &KeyboardInput,
+    held_keys: &mut HashSet<KeyCode>,
+) {
+    let down = event.state == ButtonState::Pressed;
+    if down {
+        held_keys.insert(event.key_code);
+    } else {
+        held_keys.remove(&event.key_code);
+    }
+
+    if let Some((modifier, key_codes)) = imgui_modifier(event.key_code) {
+        // The modifier remains held while the key on the other side of the keyboard is held
+        let other_down = key_codes
+            .iter()
+            .any(|key_code| *key_code != event.key_code && held_keys.contains(key_code));
+        io.add_key_event(modifier, down || other_down);
+    }
+
+    if let Some(key) = imgui_key(event.key_code) {
+        io.add_key_event(key, down);
+    }
+}
+
+// Queues the characters of the given text as ImGui input characters, skipping control
+// characters (keys such as Enter and Backspace are handled by key events instead)
+pub(crate) fn add_input_text(io: &mut imgui::Io, text: &str) {
+    for c in text.chars().filter(|c| !c.is_control()) {
+        io.add_input_character(c);
+    }
+}
+
+// Queues a Bevy mouse button event as an ImGui mouse button event
+pub(crate) fn add_mouse_button_event(io: &mut imgui::Io, event: &MouseButtonInput) {
+    let button = match event.button {
+        MouseButton::Left => imgui::MouseButton::Left,
+        MouseButton::Right => imgui::MouseButton::Right,
+        MouseButton::Middle => imgui::MouseButton::Middle,
+        MouseButton::Back => imgui::MouseButton::Extra1,
+        MouseButton::Forward => imgui::MouseButton::Extra2,
+        MouseButton::Other(_) => return,
+    };
+    io.add_mouse_button_event(button, event.state == ButtonState::Pressed);
+}
+
+// Queues the sum of the given Bevy mouse wheel events as a single ImGui mouse wheel event,
+// converting pixel deltas (e.g. from trackpads) to lines
+pub(crate) fn add_mouse_wheel_events<'a>(
+    io: &mut imgui::Io,
+    events: impl Iterator<Item = &'a MouseWheel>,
+    lines_per_pixel: f32,
+) {
+    let mut wheel = [0.0, 0.0];
+    for event in events {
+        let scale = match event.unit {
+            MouseScrollUnit::Line => 1.0,
+            MouseScrollUnit::Pixel => lines_per_pixel,
+        };
+        wheel[0] += event.x * scale;
+        wheel[1] += event.y * scale;
+    }
+    if wheel != [0.0, 0.0] {
+        io.add_mouse_wheel_event(wheel);
+    }
+}
+
+// The windows of ImGui viewports, whose input is routed to the ImGui context of the window that
+// owns them. While viewports are enabled, ImGui expects positions relative to the desktop.
+#[derive(Default)]
+pub(crate) struct ViewportInput {
+    // The owning window and desktop position of each viewport window
+    pub(crate) windows: HashMap<Entity, (Entity, Vec2)>,
+    // The desktop position of the cursor over the viewport windows of each owning window
+    pub(crate) cursor_positions: HashMap<Entity, Vec2>,
+    // The owning windows that have a focused viewport window
+    pub(crate) focused: HashSet<Entity>,
+}
+
+impl ViewportInput {
+    // Returns the window whose ImGui context receives the input of the given window
+    pub(crate) fn target(&self, window: Entity) -> Entity {
+        self.windows
+            .get(&window)
+            .map_or(window, |(owner, _)| *owner)
+    }
+
+    // Returns the desktop position of the given viewport window, or zero for any other window
+    pub(crate) fn origin(&self, window: Entity) -> Vec2 {
+        self.windows
+            .get(&window)
+            .map_or(Vec2::ZERO, |(_, origin)| *origin)
+    }
+}
+
+// Maps Bevy gamepad buttons to ImGui gamepad keys
+const GAMEPAD_BUTTONS: [(GamepadButton, Key); 14] = [
+    (GamepadButton::Start, Key::GamepadStart),
+    (GamepadButton::Select, Key::GamepadBack),
+    (GamepadButton::West, Key::GamepadFaceLeft),
+    (GamepadButton::East, Key::GamepadFaceRight),
+    (GamepadButton::North, Key::GamepadFaceUp),
+    (GamepadButton::South, Key::GamepadFaceDown),
+    (GamepadButton::DPadLeft, Key::GamepadDpadLeft),
+    (GamepadButton::DPadRight, Key::GamepadDpadRight),
+    (GamepadButton::DPadUp, Key::GamepadDpadUp),
+    (GamepadButton::DPadDown, Key::GamepadDpadDown),
+    (GamepadButton::LeftTrigger, Key::GamepadL1),
+    (GamepadButton::RightTrigger, Key::GamepadR1),
+    (GamepadButton::LeftThumb, Key::GamepadL3),
+    (GamepadButton::RightThumb, Key::GamepadR3),
+];
+
+// Maps Bevy analog gamepad buttons to ImGui gamepad keys
+const GAMEPAD_ANALOG_BUTTONS: [(GamepadButton, Key); 2] = [
+    (GamepadButton::LeftTrigger2, Key::GamepadL2),
+    (GamepadButton::RightTrigger2, Key::GamepadR2),
+];
+
+// Maps Bevy gamepad axes to the ImGui gamepad keys for their negative and positive directions
+const GAMEPAD_AXES: [(GamepadAxis, Key, Key); 4] = [
+    (
+        GamepadAxis::LeftStickX,
+        Key::GamepadLStickLeft,
+        Key::GamepadLStickRight,
+    ),
+    (
+        GamepadAxis::LeftStickY,
+        Key::GamepadLStickDown,
+        Key::GamepadLStickUp,
+    ),
+    (
+        GamepadAxis::RightStickX,
+        Key::GamepadRStickLeft,
+        Key::GamepadRStickRight,
+    ),
+    (
+        GamepadAxis::RightStickY,
+        Key::GamepadRStickDown,
+        Key::GamepadRStickUp,
+    ),
+];
+
+// Queues the state of a Bevy gamepad as ImGui gamepad key events. Passing None releases all
+// gamepad keys. ImGui ignores events that do not change the state of a key.
+pub(crate) fn add_gamepad_events(io: &mut imgui::Io, gamepad: Option<&Gamepad>) {
+    io.backend_flags
+        .set(imgui::BackendFlags::HAS_GAMEPAD, gamepad.is_some());
+
+    for (button, key) in GAMEPAD_BUTTONS {
+        io.add_key_event(key, gamepad.is_some_and(|gamepad| gamepad.pressed(button)));
+    }
+
+    for (button, key) in GAMEPAD_ANALOG_BUTTONS {
+        let value = gamepad
+            .and_then(|gamepad| gamepad.get(button))
+            .unwrap_or(0.0);
+        let down = gamepad.is_some_and(|gamepad| gamepad.pressed(button));
+        io.add_key_analog_event(key, down, value);
+    }
+
+    for (axis, negative_key, positive_key) in GAMEPAD_AXES {
+        // Bevy has already applied the dead zone of the axis at this point
+        let value = gamepad.and_then(|gamepad| gamepad.get(axis)).unwrap_or(0.0);
+        io.add_key_analog_event(negative_key, value < 0.0, (-value).max(0.0));
+        io.add_key_analog_event(positive_key, value > 0.0, value.max(0.0));
+    }
+}
+
+// Emulates the mouse with touches on a single window
+#[derive(Default)]
+pub(crate) struct TouchState {
+    // The current position of each active touch
+    positions: HashMap<u64, Vec2>,
+    // The touch that drives the mouse, if any
+    primary: Option<PrimaryTouch>,
+    // The midpoint of the touches while scrolling with two fingers
+    scroll_position: Option<Vec2>,
+}
+
+struct PrimaryTouch {
+    id: u64,
+    start_position: Vec2,
+    start_time: Duration,
+    left_down: bool,
+    long_pressed: bool,
+}
+
+impl TouchState {
+    // Whether any touches are active, in which case they drive the mouse position
+    pub(crate) fn is_active(&self) -> bool {
+        !self.positions.is_empty()
+    }
+
+    fn midpoint(&self) -> Vec2 {
+        self.positions.values().sum::<Vec2>() / self.positions.len().max(1) as f32
+    }
+
+    // Releases the mouse if it is being driven by the primary touch
+    fn cancel_primary(&mut self, io: &mut imgui::Io) {
+        if let Some(primary) = self.primary.take() {
+            if primary.left_down {
+                io.add_mouse_button_event(imgui::MouseButton::Left, false);
+            }
+        }
+    }
+
+    // Queues ImGui mouse events for the given touch events. A single touch drives the mouse,
+    // pressing the left button once it is dragged or released. Holding a touch in place emulates
+    // a right click, and dragging two touches emulates the mouse wheel.
+    pub(crate) fn update<'a>(
+        &mut self,
+        io: &mut imgui::Io,
+        events: impl Iterator<Item = &'a TouchInput>,
+        settings: &ImguiTouch,
+        now: Duration,
+        lines_per_pixel: f32,
+    ) {
+        for event in events {
+            let position = event.position;
+            match event.phase {
+                TouchPhase::Started => {
+                    self.positions.insert(event.id, position);
+                    if self.positions.len() == 1 {
+                        io.add_mouse_pos_event(position.into());
+
+                        // Without long presses, there is no need to wait before pressing
+                        let left_down = settings.long_press_duration.is_none();
+                        if left_down {
+                            io.add_mouse_button_event(imgui::MouseButton::Left, true);
+                        }
+
+                        self.primary = Some(PrimaryTouch {
+                            id: event.id,
+                            start_position: position,
+                            start_time: now,
+                            left_down,
+                            long_pressed: false,
+                        });
+                    } else if self.positions.len() == 2 && settings.two_finger_scroll {
+                        self.cancel_primary(io);
+                        self.scroll_position = Some(self.midpoint());
+                    }
+                }
+                TouchPhase::Moved => {
+                    let Some(previous_position) = self.positions.insert(event.id, position) else {
+                        continue;
+                    };
+                    if previous_position == position {
+                        continue;
+                    }
+
+                    if let Some(primary) = self
+                        .primary
+                        .as_mut()
+                        .filter(|primary| primary.id == event.id)
+                    {
+                        // Dragging presses the left button where the touch started, unless a
+                        // long press has already right clicked
+                        if !primary.left_down
+                            && !primary.long_pressed
+                            && position.distance(primary.start_position)
+                                > settings.long_press_tolerance
+                        {
+                            io.add_mouse_pos_event(primary.start_position.into());
+                            io.add_mouse_button_event(imgui::MouseButton::Left, true);
+                            primary.left_down = true;
+                        }
+                        io.add_mouse_pos_event(position.into());
+                    } else if let Some(scroll_position) = self.scroll_position {
+                        let midpoint = self.midpoint();
+                        let delta = (midpoint - scroll_position) * lines_per_pixel;
+                        io.add_mouse_wheel_event(delta.into());
+                        self.scroll_position = Some(midpoint);
+                    }
+                }
+                TouchPhase::Ended | TouchPhase::Canceled => {
+                    if self.positions.remove(&event.id).is_none() {
+                        continue;
+                    }
+
+                    if let Some(primary) = self.primary.take_if(|primary| primary.id == event.id) {
+                        if primary.left_down {
+                            io.add_mouse_button_event(imgui::MouseButton::Left, false);
+                        } else if !primary.long_pressed && event.phase == TouchPhase::Ended {
+                            // A tap clicks where the touch started
+                            io.add_mouse_pos_event(primary.start_position.into());
+                            io.add_mouse_button_event(imgui::MouseButton::Left, true);
+                            io.add_mouse_button_event(imgui::MouseButton::Left, false);
+                        }
+                    }
+
+                    if self.positions.len() < 2 {
+                        self.scroll_position = None;
+                    }
+                }
+            }
+        }
+
+        // Holding the primary touch in place emulates a right click
+        if let (Some(primary), Some(long_press_duration)) =
+            (self.primary.as_mut(), settings.long_press_duration)
+        {
+            if !primary.left_down
+                && !primary.long_pressed
+                && now.saturating_sub(primary.start_time) >= long_press_duration
+            {
+                io.add_mouse_button_event(imgui::MouseButton::Right, true);
+                io.add_mouse_button_event(imgui::MouseButton::Right, false);
+                primary.long_pressed = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{create_imgui_context, lock_imgui};
+    use bevy::input::keyboard::Key as LogicalKey;
+
+    // Starts and ends a frame, returning the mouse position and whether the left and right
+    // mouse buttons are down
+    fn frame(ctx: &mut imgui::Context) -> ([f32; 2], bool, bool) {
+        ctx.new_frame();
+        let io = ctx.io();
+        let state = (io.mouse_pos, io.mouse_down[0], io.mouse_down[1]);
+        ctx.render();
+        state
+    }
+
+    fn touch(phase: TouchPhase, x: f32, y: f32) -> TouchInput {
+        TouchInput {
+            phase,
+            position: Vec2::new(x, y),
+            window: Entity::PLACEHOLDER,
+            force: None,
+            id: 0,
+        }
+    }
+
+    fn update_touch(
+        ctx: &mut imgui::Context,
+        touch_state: &mut TouchState,
+        events: &[TouchInput],
+        now_millis: u64,
+    ) {
+        let settings = ImguiTouch::default();
+        let now = Duration::from_millis(now_millis);
+        touch_state.update(ctx.io_mut(), events.iter(), &settings, now, 1.0);
+    }
+
+    #[test]
+    fn touch_tap_clicks_where_touch_started() {
+        let _lock = lock_imgui();
+        let mut ctx = create_imgui_context();
+        let mut touch_state = TouchState::default();
+
+        let events = [
+            touch(TouchPhase::Started, 10.0, 10.0),
+            touch(TouchPhase::Moved, 13.0, 13.0),
+        ];
+        update_touch(&mut ctx, &mut touch_state, &events, 0);
+        assert_eq!(frame(&mut ctx), ([13.0, 13.0], false, false));
+
+        let events = [touch(TouchPhase::Ended, 13.0, 13.0)];
+        update_touch(&mut ctx, &mut touch_state, &events, 100);
+        assert_eq!(frame(&mut ctx), ([10.0, 10.0], true, false));
+        assert_eq!(frame(&mut ctx), ([10.0, 10.0], false, false));
+        assert!(!touch_state.is_active());
+    }
+
+    #[test]
+    fn touch_drag_presses_where_touch_started() {
+        let _lock = lock_imgui();
+        let mut ctx = create_imgui_context();
+        let mut touch_state = TouchState::default();
+
+        let events = [
+            touch(TouchPhase::Started, 10.0, 10.0),
+            touch(TouchPhase::Moved, 30.0, 10.0),
+        ];
+        update_touch(&mut ctx, &mut touch_state, &events, 0);
+        assert_eq!(frame(&mut ctx), ([10.0, 10.0], true, false));
+        assert_eq!(frame(&mut ctx), ([30.0, 10.0], true, false));
+
+        let events = [touch(TouchPhase::Ended, 30.0, 10.0)];
+        update_touch(&mut ctx, &mut touch_state, &events, 100);
+        assert_eq!(frame(&mut ctx), ([30.0, 10.0], false, false));
+    }
+
+    #[test]
+    fn touch_long_press_right_clicks() {
+        let _lock = lock_imgui();
+        let mut ctx = create_imgui_context();
+        let mut touch_state = TouchState::default();
+
+        let events = [touch(TouchPhase::Started, 10.0, 10.0)];
+        update_touch(&mut ctx, &mut touch_state, &events, 0);
+        update_touch(&mut ctx, &mut touch_state, &[], 600);
+        assert_eq!(frame(&mut ctx), ([10.0, 10.0], false, true));
+
+        // Releasing the touch after a long press does not also click
+        let events = [touch(TouchPhase::Ended, 10.0, 10.0)];
+        update_touch(&mut ctx, &mut touch_state, &events, 700);
+        assert_eq!(frame(&mut ctx), ([10.0, 10.0], false, false));
+        assert_eq!(frame(&mut ctx), ([10.0, 10.0], false, false));
+    }
+
+    #[test]
+    fn modifier_remains_held_while_other_side_is_held() {
+        let _lock = lock_imgui();
+        let mut ctx = create_imgui_context();
+        let mut held_keys = HashSet::new();
+
+        let mut key_event = |ctx: &mut imgui::Context, key_code, state| {
+            let event = KeyboardInput {
+                key_code,
+                logical_key: LogicalKey::Shift,
+                state,
+                text: None,
+                repeat: false,
+                window: Entity::PLACEHOLDER,
+            };
+            add_keyboard_event(ctx.io_mut(), &event, &mut held_keys);
+        };
+
+        key_event(&mut ctx, KeyCode::ShiftLeft, ButtonState::Pressed);
+        key_event(&mut ctx, KeyCode::ShiftRight, ButtonState::Pressed);
+        key_event(&mut ctx, KeyCode::ShiftRight, ButtonState::Released);
+        ctx.new_frame();
+        assert!(ctx.io().key_shift);
+        ctx.render();
+
+        key_event(&mut ctx, KeyCode::ShiftLeft, ButtonState::Released);
+        ctx.new_frame();
+        assert!(!ctx.io().key_shift);
+        ctx.render();
+    }
+}
@@ -40,32 +40,67 @@ use bevy::{
         core_2d::graph::{Core2d, Node2d},
         core_3d::graph::{Core3d, Node3d},
     },
-    ecs::system::SystemState,
     input::{
-        keyboard::{Key, KeyboardInput},
-        ButtonState,
+        keyboard::KeyboardInput, mouse::MouseButtonInput, touch::TouchInput, ButtonState,
+        InputSystem,
     },
     prelude::*,
     render::{
+        camera::{ExtractedCamera, NormalizedRenderTarget},
+        graph::CameraDriverLabel,
         render_asset::RenderAssets,
-        render_graph::{Node, NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel},
+        render_graph::{
+            Node, NodeRunError, RenderGraph, RenderGraphApp, RenderGraphContext, RenderLabel,
+        },
         renderer::{RenderContext, RenderDevice, RenderQueue},
         texture::GpuImage,
         view::ExtractedWindows,
         Extract, Render, RenderApp, RenderSet,
     },
-    window::PrimaryWindow,
+    window::{Ime, PrimaryWindow},
 };
-use imgui::{FontSource, OwnedDrawData, TextureId};
+use imgui::{OwnedDrawData, TextureId};
+mod clipboard;
+#[cfg(feature = "cursor")]
+mod cursor;
+#[cfg(feature = "docking")]
+mod docking;
+mod fonts;
 mod imgui_wgpu_rs_local;
-use imgui_wgpu_rs_local::{Renderer, RendererConfig, Texture};
+mod input;
+mod settings;
+mod style;
+mod ui;
+#[cfg(feature = "docking")]
+mod viewports;
+pub use clipboard::ImguiClipboard;
+#[cfg(feature = "docking")]
+pub use docking::imgui_dockspace_over_window;
+pub use fonts::{
+    ImguiFont, ImguiFontAtlasConfig, ImguiFontAtlasRebuilt, ImguiFontSource, ImguiGlyphRanges,
+};
+use fonts::{RegisteredGlyphs, ResolvedFonts};
+use imgui_wgpu_rs_local::{DrawCallback, Renderer, RendererConfig, Texture};
+pub use settings::{
+    ImguiFileSettingsStorage, ImguiMemorySettingsStorage, ImguiSettingsHandler,
+    ImguiSettingsStorage, ImguiSettingsWriter,
+};
 use std::{
-    collections::HashMap,
+    cell::Cell,
+    collections::{HashMap, HashSet},
     ops::{Deref, DerefMut},
     path::PathBuf,
     ptr::NonNull,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
 };
+pub use style::{ImguiStyle, ImguiStylePreset};
+pub use ui::{ImguiUi, ImguiUiQueue};
+#[cfg(feature = "docking")]
+pub use viewports::ImguiViewportWindow;
 use wgpu::{
     CommandEncoder, LoadOp, Operations, RenderPass, RenderPassColorAttachment,
     RenderPassDescriptor, StoreOp, TextureFormat,
@@ -74,15 +109,410 @@ use wgpu::{
 /// The ImGui context resource.
 ///
 /// This should be added to your Bevy app as a `NonSendMut` resource (as it is not thread safe).
+/// Systems that only submit UI to the primary window can use the `ImguiUi` system parameter
+/// instead, or queue UI from any thread with `ImguiUiQueue`.
 ///
 /// You can use this object to obtain a reference to the underlying `imgui::Ui` object for submitting
-/// UI elements to imgui. This should be done during the Update and PostUpdate phase only.
+/// UI elements to imgui. This should be done during the Update and PostUpdate phase only, or
+/// between `ImguiSet::NewFrame` and `ImguiSet::EndFrame`.
+///
+/// The primary window always has an ImGui context, which is accessed via `ui`. Secondary windows
+/// marked with the `ImguiWindow` component, and entities with an `ImguiImageTarget` component,
+/// have their own ImGui context, which is accessed via `window_ui`.
 pub struct ImguiContext {
-    ctx: RwLock<imgui::Context>,
-    ui: Option<NonNull<imgui::Ui>>,
+    targets: HashMap<Entity, Box<ImguiTargetContext>>,
+    primary_window: Option<Entity>,
+    plugin: ImguiPlugin,
     textures: HashMap<imgui::TextureId, Arc<StrongHandle>>,
     texture_modify: RwLock<ImguiTextureModifyState>,
+    draw_callbacks: HashMap<ImguiDrawCallbackId, Arc<dyn ImguiDrawCallback>>,
+    next_draw_callback_id: usize,
+    font_atlas_config: ImguiFontAtlasConfig,
+    // Whether `font_atlas_config` or `registered_glyphs` has changed since `fonts` was resolved
+    font_atlas_config_changed: bool,
+    registered_glyphs: RegisteredGlyphs,
+    // The fonts that each target's font atlas is built from
+    fonts: Arc<ResolvedFonts>,
+    // Incremented whenever `fonts` changes, so that each target's font atlas is rebuilt
+    font_generation: u64,
+    // Whether the next resolved fonts replace fonts that were requested or still loading, so
+    // that `ImguiFontAtlasRebuilt` is sent once they are in use
+    font_rebuild_requested: bool,
+    // The `font_generation` to send `ImguiFontAtlasRebuilt` for once every target has rebuilt
+    // its font atlas
+    pending_rebuilt_font_generation: Option<u64>,
+    style: ImguiStyle,
+    style_asset: Option<Handle<ImguiStyle>>,
+    // Whether `style_asset` has been set or modified since it was last applied
+    style_asset_changed: bool,
+    // Incremented whenever `style` changes, so that it is applied to each target
+    style_generation: u64,
+    // Settings handlers for targets that do not have an ImGui context yet, where None is the
+    // primary window
+    pending_settings_handlers: Vec<(Option<Entity>, Box<dyn ImguiSettingsHandler>)>,
+}
+
+// The ImGui context belonging to a single window or image target
+struct ImguiTargetContext {
+    ctx: RwLock<imgui::Context>,
+    raw: *mut imgui::sys::ImGuiContext,
+    ui: Option<NonNull<imgui::Ui>>,
     rendered_draw_data: RwLock<OwnedDrawData>,
+    focused: bool,
+    // Written by ImGui via `imgui_set_platform_ime_data` when rendering a frame
+    platform_ime_data: Box<Cell<PlatformImeData>>,
+    want_text_input: bool,
+    touch: input::TouchState,
+    // The keys that are held in this context, according to the keyboard events routed to it
+    held_keys: HashSet<KeyCode>,
+    // The id of each configured font in this context's font atlas, written during Extract
+    font_ids: RwLock<Vec<imgui::FontId>>,
+    // The fonts that the font atlas was built from, which are kept alive along with the atlas as
+    // it refers to their glyph ranges
+    atlas_fonts: RwLock<Option<Arc<ResolvedFonts>>>,
+    // The `ImguiContext::font_generation` that the font atlas was built from
+    font_generation: AtomicU64,
+    // ImGui's default style, which `ImguiStyle`s are applied to before scaling
+    default_style: imgui::Style,
+    // The display scale that the sizes of the current style are scaled by, written during Extract
+    style_scale: Cell<f32>,
+    // The `ImguiContext::style_generation` that the current style was applied from
+    style_generation: u64,
+    ini_filename: Option<PathBuf>,
+    settings_storage: Arc<dyn ImguiSettingsStorage>,
+    // The settings loaded when the context was created, for handlers that are added later
+    loaded_settings: String,
+    settings_handlers: Vec<Box<dyn ImguiSettingsHandler>>,
+    // The seconds left until the settings are saved because a settings handler is dirty
+    settings_handlers_save_timer: Option<f32>,
+    // Whether the settings have been saved for the app exiting, so they are not saved again when
+    // the context is dropped
+    final_settings_saved: bool,
+    // The viewports of this context, if viewports are enabled for it
+    #[cfg(feature = "docking")]
+    viewports: Option<std::rc::Rc<std::cell::RefCell<viewports::ViewportState>>>,
+}
+
+// Where ImGui wants the IME candidate window to be shown, in logical pixels
+#[derive(Clone, Copy, Default)]
+struct PlatformImeData {
+    want_visible: bool,
+    input_pos: [f32; 2],
+    input_line_height: f32,
+}
+
+// Called by ImGui when the text cursor of the active text field changes
+unsafe extern "C" fn imgui_set_platform_ime_data(
+    _viewport: *mut imgui::sys::ImGuiViewport,
+    data: *mut imgui::sys::ImGuiPlatformImeData,
+) {
+    let io = imgui::sys::igGetIO();
+    let platform_ime_data = (*io).UserData as *const Cell<PlatformImeData>;
+    if let (Some(platform_ime_data), Some(data)) = (platform_ime_data.as_ref(), data.as_ref()) {
+        platform_ime_data.set(PlatformImeData {
+            want_visible: data.WantVisible,
+            input_pos: [data.InputPos.x, data.InputPos.y],
+            input_line_height: data.InputLineHeight,
+        });
+    }
+}
+
+impl ImguiTargetContext {
+    fn new(ini_filename: Option<PathBuf>, plugin: &ImguiPlugin) -> Self {
+        // Dear ImGui only allows a new context to be created when there is no active
+        // context, so deactivate whichever context is currently active first
+        unsafe { imgui::sys::igSetCurrentContext(std::ptr::null_mut()) };
+        let mut ctx = imgui::Context::create();
+        let raw = unsafe { imgui::sys::igGetCurrentContext() };
+        ctx.set_clipboard_backend(plugin.clipboard.backend());
+
+        #[cfg(feature = "docking")]
+        ctx.io_mut()
+            .config_flags
+            .set(imgui::ConfigFlags::DOCKING_ENABLE, plugin.docking);
+
+        // Settings are loaded and saved through the settings storage rather than by ImGui
+        ctx.set_ini_filename(None);
+        ctx.io_mut().ini_saving_rate = plugin.settings_autosave_interval.as_secs_f32();
+        let loaded_settings = ini_filename
+            .as_deref()
+            .and_then(|ini_filename| plugin.settings_storage.load(ini_filename))
+            .unwrap_or_default();
+        // ImGui treats a size of zero as a null terminated string, so empty settings are skipped
+        if !loaded_settings.is_empty() {
+            ctx.load_ini_settings(&loaded_settings);
+        }
+        let default_style = *ctx.style();
+
+        // Build a default font atlas so that a frame can be started before the renderer
+        // for this target is created (and the atlas rebuilt at the correct display scale)
+        // during Extract
+        ctx.fonts().build_rgba32_texture();
+
+        // Receive the position of the active text field, for placing the IME candidate window.
+        // This is stored in the user data of the IO, as the backend platform user data is used
+        // by the platform backend of viewports.
+        let platform_ime_data = Box::new(Cell::new(PlatformImeData::default()));
+        unsafe {
+            let io = imgui::sys::igGetIO();
+            (*io).UserData = platform_ime_data.as_ref() as *const _ as *mut _;
+            (*io).SetPlatformImeDataFn = Some(imgui_set_platform_ime_data);
+        }
+
+        Self {
+            ctx: RwLock::new(ctx),
+            raw,
+            ui: None,
+            rendered_draw_data: default(),
+            focused: false,
+            platform_ime_data,
+            want_text_input: false,
+            touch: default(),
+            held_keys: default(),
+            font_ids: default(),
+            atlas_fonts: default(),
+            font_generation: AtomicU64::new(0),
+            default_style,
+            style_scale: Cell::new(1.0),
+            style_generation: 0,
+            ini_filename,
+            settings_storage: plugin.settings_storage.clone(),
+            loaded_settings,
+            settings_handlers: Vec::new(),
+            settings_handlers_save_timer: None,
+            final_settings_saved: false,
+            #[cfg(feature = "docking")]
+            viewports: None,
+        }
+    }
+
+    // Applies the given style, scaled to the current display scale. The context must be active.
+    fn apply_style(&mut self, style: &ImguiStyle, style_generation: u64) {
+        let mut imgui_style = self.default_style;
+        style.apply(&mut imgui_style);
+        imgui_style.scale_all_sizes(self.style_scale.get());
+        *self.ctx.get_mut().unwrap().style_mut() = imgui_style;
+        self.style_generation = style_generation;
+    }
+
+    // Saves the ini settings if ImGui wants them saved, if a settings handler has been dirty for
+    // the autosave interval, or if forced. The context must be active.
+    fn save_settings(&mut self, force: bool) {
+        let Some(ini_filename) = &self.ini_filename else {
+            return;
+        };
+        let ctx = self.ctx.get_mut().unwrap();
+
+        // Dirty settings handlers are saved after the autosave interval, as ImGui does with its
+        // own settings
+        if self.settings_handlers_save_timer.is_none()
+            && self
+                .settings_handlers
+                .iter_mut()
+                .any(|handler| handler.is_dirty())
+        {
+            self.settings_handlers_save_timer = Some(ctx.io().ini_saving_rate);
+        }
+        if let Some(timer) = &mut self.settings_handlers_save_timer {
+            *timer -= ctx.io().delta_time;
+        }
+        let handlers_want_save = self
+            .settings_handlers_save_timer
+            .is_some_and(|timer| timer <= 0.0);
+
+        if force || handlers_want_save || ctx.io().want_save_ini_settings {
+            let mut settings = String::new();
+            ctx.save_ini_settings(&mut settings);
+            for handler in self.settings_handlers.iter_mut() {
+                settings::write_settings(&mut settings, handler.as_mut());
+            }
+            self.settings_storage.save(ini_filename, &settings);
+
+            // ImGui leaves clearing the flag to the application when it does not save the ini
+            // file itself
+            ctx.io_mut().want_save_ini_settings = false;
+            self.settings_handlers_save_timer = None;
+        }
+    }
+
+    // Saves the ini settings one final time, unless they have already been saved for the app
+    // exiting. The context must be active.
+    fn save_final_settings(&mut self) {
+        if !std::mem::replace(&mut self.final_settings_saved, true) {
+            self.save_settings(true);
+        }
+    }
+
+    // Requests that the ini settings are saved at the end of the frame
+    fn request_save(&mut self) {
+        // The IO of a context is accessed through the active context
+        self.activate();
+        self.ctx.get_mut().unwrap().io_mut().want_save_ini_settings = true;
+    }
+
+    // Adds a settings handler, reading its entries from the settings loaded at creation
+    fn add_settings_handler(&mut self, mut handler: Box<dyn ImguiSettingsHandler>) {
+        settings::read_settings(&self.loaded_settings, handler.as_mut());
+        self.settings_handlers.push(handler);
+    }
+
+    // Makes this the active Dear ImGui context. All imgui calls operate on the active
+    // context, so this must be called before using the context or its Ui.
+    fn activate(&self) {
+        unsafe { imgui::sys::igSetCurrentContext(self.raw) };
+    }
+}
+
+impl Drop for ImguiTargetContext {
+    fn drop(&mut self) {
+        // imgui::Context ends the frame of the active context when dropped
+        self.activate();
+        self.save_final_settings();
+    }
+}
+
+/// Marks a secondary window as having its own ImGui context.
+///
+/// The primary window always has an ImGui context. Add this component to any other window
+/// entity to give it a separate context, with its own input, display scale and rendering.
+/// UI for the window can then be submitted via `ImguiContext::window_ui`.
+///
+/// As with the primary window, the UI is drawn on top of the last camera that renders to the window.
+#[derive(Component, Clone, Default)]
+pub struct ImguiWindow {
+    /// Sets the path to the ini file for this window's context.
+    /// Pass None to disable automatic .Ini saving (default is None).
+    pub ini_filename: Option<PathBuf>,
+}
+
+/// Gives an entity its own ImGui context that is rendered into an image, rather than a window.
+///
+/// This can be used to display UI on in-world surfaces, to composite UI in a post-process, or to
+/// capture UI offscreen. The image must be created with `TextureUsages::RENDER_ATTACHMENT`, and the
+/// display size of the context follows the size of the image. Image targets do not receive input.
+/// UI for the image can be submitted via `ImguiContext::window_ui`, passing the entity that this
+/// component is added to.
+///
+/// If a camera renders to the image, the UI is drawn on top of the last camera that renders to it.
+/// Otherwise, the image is cleared and the UI drawn before any cameras are rendered, so that the
+/// result can be sampled by cameras in the same frame.
+#[derive(Component, Clone)]
+pub struct ImguiImageTarget {
+    /// The image to render to.
+    pub image: Handle<Image>,
+
+    /// The scale factor of the image, corresponding to the scale factor of a window (default is 1.0).
+    pub scale_factor: f32,
+
+    /// How to clear the image before drawing when no camera renders to it
+    /// (default is `ClearColorConfig::Custom(Color::NONE)`).
+    pub clear_color: ClearColorConfig,
+
+    /// Sets the path to the ini file for this image's context.
+    /// Pass None to disable automatic .Ini saving (default is None).
+    pub ini_filename: Option<PathBuf>,
+}
+
+impl ImguiImageTarget {
+    /// Creates an image target with default settings for the given image.
+    pub fn new(image: Handle<Image>) -> Self {
+        Self {
+            image,
+            scale_factor: 1.0,
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ini_filename: None,
+        }
+    }
+}
+
+/// Custom wgpu rendering that can be embedded in an ImGui draw list.
+///
+/// Register a callback with `ImguiContext::register_draw_callback`, and then add it to the draw
+/// list of the current ImGui window with `ImguiDrawCallbackId::add_to_window_draw_list`. The
+/// callback is invoked in the render world, at that point in the draw list, with the render pass
+/// that ImGui is being drawn with. Any closure with a matching signature can be used as a callback.
+///
+/// The imgui pipeline, uniforms and vertex/index buffers are rebound after the callback, so the
+/// callback is free to change any render pass state.
+pub trait ImguiDrawCallback: Send + Sync + 'static {
+    /// Draws into the render pass. The scissor rect of the render pass is set to the clip rect.
+    fn draw(&self, render_pass: &mut RenderPass<'_>, info: &ImguiDrawCallbackInfo);
+}
+
+impl<F> ImguiDrawCallback for F
+where
+    F: Fn(&mut RenderPass<'_>, &ImguiDrawCallbackInfo) + Send + Sync + 'static,
+{
+    fn draw(&self, render_pass: &mut RenderPass<'_>, info: &ImguiDrawCallbackInfo) {
+        self(render_pass, info)
+    }
+}
+
+/// The render state passed to an `ImguiDrawCallback`.
+pub struct ImguiDrawCallbackInfo<'a> {
+    /// The render device.
+    pub device: &'a RenderDevice,
+
+    /// The render queue.
+    pub queue: &'a RenderQueue,
+
+    /// The window or image target entity that ImGui is being drawn to.
+    pub target: Entity,
+
+    /// The texture format of the render pass' color attachment.
+    pub texture_format: TextureFormat,
+
+    /// The clip rect at this point in the draw list, in framebuffer pixels
+    /// (min x, min y, max x, max y). This may extend outside of the framebuffer.
+    pub clip_rect: [f32; 4],
+
+    /// The size of the framebuffer in pixels.
+    pub framebuffer_size: [f32; 2],
+}
+
+/// Identifies a draw callback registered with `ImguiContext::register_draw_callback`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImguiDrawCallbackId(usize);
+
+impl ImguiDrawCallbackId {
+    /// Adds the draw callback to the draw list of the current ImGui window.
+    ///
+    /// The callback is clipped to the current clip rect of the window's draw list.
+    pub fn add_to_window_draw_list(self, _ui: &imgui::Ui) {
+        // The Ui reference ensures that a frame is being rendered in the active context
+        unsafe {
+            let draw_list = imgui::sys::igGetWindowDrawList();
+            imgui::sys::ImDrawList_AddCallback(
+                draw_list,
+                Some(imgui_draw_callback_marker),
+                self.0 as *mut std::ffi::c_void,
+            );
+            // Ask the renderer to rebind its state after the callback
+            imgui::sys::ImDrawList_AddCallback(
+                draw_list,
+                Some(std::mem::transmute::<
+                    isize,
+                    unsafe extern "C" fn(
+                        *const imgui::sys::ImDrawList,
+                        *const imgui::sys::ImDrawCmd,
+                    ),
+                >(IMGUI_DRAW_CALLBACK_RESET_RENDER_STATE)),
+                std::ptr::null_mut(),
+            );
+        }
+    }
+}
+
+// The value of ImDrawCallback_ResetRenderState in Dear ImGui, which imgui-rs reports as
+// `DrawCmd::ResetRenderState`
+const IMGUI_DRAW_CALLBACK_RESET_RENDER_STATE: isize = -1;
+
+// Identifies draw callbacks added by `ImguiDrawCallbackId`. This is never actually called, as
+// the callback is dispatched to the registered `ImguiDrawCallback` during rendering instead.
+unsafe extern "C" fn imgui_draw_callback_marker(
+    _parent_list: *const imgui::sys::ImDrawList,
+    _cmd: *const imgui::sys::ImDrawCmd,
+) {
 }
 
 #[derive(Default)]
@@ -93,22 +523,153 @@ struct ImguiTextureModifyState {
 }
 
 impl ImguiContext {
-    /// Provides mutable access to the underlying `imgui::Ui` object.
+    /// Provides mutable access to the underlying `imgui::Ui` object of the primary window.
     ///
-    /// Use this to submit UI elements to imgui.
+    /// Use this to submit UI elements to imgui. This panics if the primary window does not have an
+    /// ImGui context, or if an imgui frame is not currently being rendered (see `ImguiSet`),
+    /// reporting the location of the call. Use `try_ui` where this may be the case.
+    #[track_caller]
     pub fn ui(&mut self) -> &mut imgui::Ui {
-        unsafe {
-            self.ui
-                .expect("Not currently rendering an imgui frame!")
-                .as_mut()
+        let primary_window = self.primary_window.expect(
+            "No primary window imgui context! Use ImguiContext::try_ui where there may be none.",
+        );
+        self.window_ui(primary_window).expect(
+            "Not currently rendering an imgui frame! Systems that use the imgui Ui must run after \
+            ImguiSet::NewFrame and before ImguiSet::EndFrame (e.g. in Update or PostUpdate), or \
+            use ImguiContext::try_ui.",
+        )
+    }
+
+    /// Provides mutable access to the underlying `imgui::Ui` object of the primary window.
+    ///
+    /// Returns None if the primary window does not have an ImGui context, or if an imgui frame is
+    /// not currently being rendered (e.g. in `First`, or in `PreUpdate` before
+    /// `ImguiSet::NewFrame`).
+    pub fn try_ui(&mut self) -> Option<&mut imgui::Ui> {
+        self.window_ui(self.primary_window?)
+    }
+
+    /// Provides mutable access to the underlying `imgui::Ui` object of the given window, or of
+    /// the given entity's `ImguiImageTarget`.
+    ///
+    /// Returns None if the entity does not have an ImGui context (see `ImguiWindow` and
+    /// `ImguiImageTarget`), or if an imgui frame is not currently being rendered.
+    pub fn window_ui(&mut self, window: Entity) -> Option<&mut imgui::Ui> {
+        let context = self.targets.get_mut(&window)?;
+        context.activate();
+        context.ui.map(|mut ui| unsafe { ui.as_mut() })
+    }
+
+    /// Returns the id of the font at the given index of `ImguiFontAtlasConfig::fonts` in the
+    /// font atlas of the primary window, for use with `imgui::Ui::push_font`. Fonts that are
+    /// merged into a previous font return the id of that font.
+    ///
+    /// Returns None if there is no such font, or if the font atlas has not yet been built with
+    /// the configured fonts (in which case index 0 refers to ImGui's built-in font).
+    pub fn font_id(&self, index: usize) -> Option<imgui::FontId> {
+        self.window_font_id(self.primary_window?, index)
+    }
+
+    /// Returns the id of the font at the given index of `ImguiFontAtlasConfig::fonts` in the
+    /// font atlas of the given window, or of the given entity's `ImguiImageTarget`. See `font_id`.
+    pub fn window_font_id(&self, window: Entity, index: usize) -> Option<imgui::FontId> {
+        let context = self.targets.get(&window)?;
+        context.font_ids.read().unwrap().get(index).copied()
+    }
+
+    /// Returns the current configuration of the font atlas.
+    pub fn font_atlas_config(&self) -> &ImguiFontAtlasConfig {
+        &self.font_atlas_config
+    }
+
+    /// Requests that the font atlas of every ImGui context is rebuilt with the given
+    /// configuration (e.g. to change the font size from a settings menu). The current fonts
+    /// remain in use until every font asset in the new configuration has loaded, after which the
+    /// atlas is rebuilt at the next Extract and `ImguiFontAtlasRebuilt` is sent. Registered Bevy
+    /// textures are unaffected.
+    pub fn request_font_rebuild(&mut self, config: ImguiFontAtlasConfig) {
+        self.font_atlas_config = config;
+        self.font_atlas_config_changed = true;
+        self.font_rebuild_requested = true;
+    }
+
+    /// Adds the characters of the given text to fonts with `ImguiGlyphRanges::Registered`. If any
+    /// characters are new, the font atlas of every ImGui context is rebuilt at the next Extract.
+    ///
+    /// Glyphs are not collected from the text passed to widgets, so every string that may contain
+    /// characters outside of Latin-1 must be registered before it is drawn. Registering strings
+    /// ahead of time (e.g. when localised strings are loaded) avoids repeated rebuilds.
+    pub fn register_glyphs(&mut self, text: &str) {
+        if self.registered_glyphs.add(text) && self.font_atlas_config.uses_registered_glyphs() {
+            self.font_atlas_config_changed = true;
+            self.font_rebuild_requested = true;
+        }
+    }
+
+    /// Applies the given style to every ImGui context, replacing any style asset set with
+    /// `set_style_asset`. Sizes are scaled by the display scale of each window or image target.
+    pub fn set_style(&mut self, style: ImguiStyle) {
+        self.style = style;
+        self.style_asset = None;
+        self.style_generation += 1;
+    }
+
+    /// Applies the given style asset to every ImGui context once it has loaded, and again
+    /// whenever the asset is modified (e.g. by hot reloading).
+    pub fn set_style_asset(&mut self, style: Handle<ImguiStyle>) {
+        self.style_asset = Some(style);
+        self.style_asset_changed = true;
+    }
+
+    /// Adds a handler that persists game-specific state in the ini settings of the primary
+    /// window. The handler reads its entries from the saved settings as soon as the primary
+    /// window's ImGui context exists, and writes them whenever the settings are saved.
+    pub fn add_settings_handler(&mut self, handler: impl ImguiSettingsHandler) {
+        self.add_settings_handler_for(None, Box::new(handler));
+    }
+
+    /// Adds a handler that persists game-specific state in the ini settings of the given window,
+    /// or of the given entity's `ImguiImageTarget`. See `add_settings_handler`.
+    pub fn add_window_settings_handler(
+        &mut self,
+        window: Entity,
+        handler: impl ImguiSettingsHandler,
+    ) {
+        self.add_settings_handler_for(Some(window), Box::new(handler));
+    }
+
+    fn add_settings_handler_for(
+        &mut self,
+        window: Option<Entity>,
+        handler: Box<dyn ImguiSettingsHandler>,
+    ) {
+        match window
+            .or(self.primary_window)
+            .and_then(|window| self.targets.get_mut(&window))
+        {
+            Some(target_context) => target_context.add_settings_handler(handler),
+            None => self.pending_settings_handlers.push((window, handler)),
+        }
+    }
+
+    /// Requests that the ini settings of every ImGui context are saved at the end of the frame,
+    /// e.g. after state persisted by an `ImguiSettingsHandler` has changed.
+    pub fn request_settings_save(&mut self) {
+        for target_context in self.targets.values_mut() {
+            target_context.request_save();
         }
     }
 
+    /// Returns the entities (windows and image targets) that currently have an ImGui context.
+    pub fn targets(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.targets.keys().copied()
+    }
+
     /// Register a Bevy texture with ImGui. The provided Handle must be strong, and
     /// the texture will be kept alive until `unregister_bevy_texture` is called to
     /// release the texture.
     /// This function returns an `imgui::TextureId` that can be immediately used with
-    /// the underlying ImGui context.
+    /// any of the underlying ImGui contexts.
     pub fn register_bevy_texture(&mut self, handle: Handle<Image>) -> imgui::TextureId {
         // We require strong handles here to ensure the image is alive at the point that
         // it is registered. Once it is registered, the we maintain a strong handle to
@@ -126,6 +687,31 @@ impl ImguiContext {
         }
     }
 
+    /// Register a custom draw callback with ImGui. The callback is kept alive until
+    /// `unregister_draw_callback` is called.
+    /// This function returns an `ImguiDrawCallbackId` that can be immediately added to
+    /// the draw list of any of the underlying ImGui contexts.
+    pub fn register_draw_callback(
+        &mut self,
+        callback: impl ImguiDrawCallback,
+    ) -> ImguiDrawCallbackId {
+        let result = ImguiDrawCallbackId(self.next_draw_callback_id);
+        self.draw_callbacks.insert(result, Arc::new(callback));
+        self.next_draw_callback_id += 1;
+        result
+    }
+
+    /// Unregister a custom draw callback with ImGui. Any draw lists that still reference
+    /// the callback will skip it.
+    pub fn unregister_draw_callback(&mut self, id: ImguiDrawCallbackId) {
+        self.draw_callbacks.remove(&id);
+    }
+
+    /// Sets which gamepad drives ImGui's gamepad navigation, overriding `ImguiPlugin::gamepad`.
+    pub fn set_gamepad(&mut self, gamepad: ImguiGamepad) {
+        self.plugin.gamepad = gamepad;
+    }
+
     /// Unregister a Bevy texture with ImGui. The texture must have previously been
     /// registered with `register_bevy_texture` - this function expects the
     /// `imgui::TextureId` returned by `register_bevy_texture` to be to be passed here.
@@ -145,15 +731,34 @@ struct NonSendHack;
 
 #[derive(Resource)]
 struct ImguiRenderContext {
+    targets: HashMap<Entity, ImguiTargetRenderContext>,
+    plugin: ImguiPlugin,
+    // Maps each view that should draw ImGui to the target it renders to
+    views: HashMap<Entity, Entity>,
+    draw_callbacks: HashMap<ImguiDrawCallbackId, Arc<dyn ImguiDrawCallback>>,
+}
+
+// The render state for a single window or image target's ImGui context
+struct ImguiTargetRenderContext {
+    target: ImguiRenderTarget,
     renderer: RwLock<Renderer>,
     texture_format: TextureFormat,
     draw: OwnedDrawDataWrap,
-    plugin: ImguiPlugin,
     display_scale: f32,
     textures_to_add: HashMap<TextureId, Arc<StrongHandle>>,
     textures_to_remove: Vec<TextureId>,
 }
 
+enum ImguiRenderTarget {
+    Window,
+    Image {
+        image: AssetId<Image>,
+        clear_color: ClearColorConfig,
+        // Whether a camera renders to the image, in which case ImGui is drawn by that camera's view
+        has_view: bool,
+    },
+}
+
 // OwnedDrawData is erroneously not marked Send, do this to make it so.
 #[derive(Default)]
 struct OwnedDrawDataWrap(imgui::OwnedDrawData);
@@ -169,58 +774,151 @@ struct ImGuiNode;
 impl ImGuiNode {
     fn create_render_pass<'a>(
         command_encoder: &'a mut CommandEncoder,
-        world: &'a World,
-    ) -> Result<RenderPass<'a>, ()> {
-        let extracted_windows = &world.get_resource::<ExtractedWindows>().unwrap();
-        let Some(primary) = extracted_windows.primary else {
-            return Err(()); // No primary window
-        };
-        let Some(extracted_window) = extracted_windows.windows.get(&primary) else {
-            return Err(()); // No primary window
-        };
-        let swap_chain_texture_view = if let Some(swap_chain_texture_view) =
-            extracted_window.swap_chain_texture_view.as_ref()
-        {
-            swap_chain_texture_view
-        } else {
-            return Err(()); // No swapchain texture
-        };
-
-        Ok(command_encoder.begin_render_pass(&RenderPassDescriptor {
+        view: &'a wgpu::TextureView,
+        load: LoadOp<wgpu::Color>,
+    ) -> RenderPass<'a> {
+        command_encoder.begin_render_pass(&RenderPassDescriptor {
             label: None,
             color_attachments: &[Some(RenderPassColorAttachment {
-                view: swap_chain_texture_view,
+                view,
                 resolve_target: None,
                 ops: Operations {
-                    load: LoadOp::Load,
+                    load,
                     store: StoreOp::Store,
                 },
             })],
             depth_stencil_attachment: None,
             ..Default::default()
-        }))
+        })
+    }
+
+    fn render_target(
+        context: &ImguiRenderContext,
+        target: Entity,
+        target_context: &ImguiTargetRenderContext,
+        view: &wgpu::TextureView,
+        load: LoadOp<wgpu::Color>,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) {
+        let queue = world.get_resource::<RenderQueue>().unwrap();
+        let render_device = world.get_resource::<RenderDevice>().unwrap();
+        let command_encoder = render_context.command_encoder();
+        let wgpu_device = render_device.wgpu_device();
+        let mut renderer = target_context.renderer.write().unwrap();
+        let mut rpass = ImGuiNode::create_render_pass(command_encoder, view, load);
+
+        // Dispatch draw callbacks added by `ImguiDrawCallbackId` to the registered callback
+        let mut callback_handler = |rpass: &mut RenderPass<'_>, callback: &DrawCallback| {
+            if !std::ptr::eq(
+                callback.callback as *const (),
+                imgui_draw_callback_marker as *const (),
+            ) {
+                return;
+            }
+            let id = ImguiDrawCallbackId(callback.user_data as usize);
+            if let Some(draw_callback) = context.draw_callbacks.get(&id) {
+                draw_callback.draw(
+                    rpass,
+                    &ImguiDrawCallbackInfo {
+                        device: render_device,
+                        queue,
+                        target,
+                        texture_format: target_context.texture_format,
+                        clip_rect: callback.clip_rect,
+                        framebuffer_size: callback.fb_size,
+                    },
+                );
+            }
+        };
+
+        if let Some(draw_data) = target_context.draw.0.draw_data() {
+            renderer
+                .render(
+                    draw_data,
+                    queue,
+                    wgpu_device,
+                    &mut rpass,
+                    &mut callback_handler,
+                )
+                .unwrap();
+        }
     }
 }
 
 impl Node for ImGuiNode {
     fn run(
         &self,
-        _graph: &mut RenderGraphContext,
+        graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
         world: &World,
     ) -> Result<(), NodeRunError> {
         let context = world.resource::<ImguiRenderContext>();
-        let queue = world.get_resource::<RenderQueue>().unwrap();
-        let render_device = world.get_resource::<RenderDevice>().unwrap();
-        let command_encoder = render_context.command_encoder();
-        let wgpu_device = render_device.wgpu_device();
-        let mut renderer = context.renderer.write().unwrap();
-        if let Ok(mut rpass) = ImGuiNode::create_render_pass(command_encoder, world) {
-            if let Some(draw_data) = context.draw.0.draw_data() {
-                renderer
-                    .render(draw_data, queue, wgpu_device, &mut rpass)
-                    .unwrap();
+
+        // This node is added to the main render graph (with no view) to draw image targets that
+        // no camera renders to, and to the 2D and 3D camera graphs to draw windows and image
+        // targets on top of the last view that renders to them
+        let Some(view) = graph.get_view_entity() else {
+            let gpu_images = world.resource::<RenderAssets<GpuImage>>();
+            for (target, target_context) in context.targets.iter() {
+                let ImguiRenderTarget::Image {
+                    image,
+                    clear_color,
+                    has_view: false,
+                } = &target_context.target
+                else {
+                    continue;
+                };
+                let Some(gpu_image) = gpu_images.get(*image) else {
+                    continue;
+                };
+                let load = match clear_color {
+                    ClearColorConfig::Default => {
+                        LoadOp::Clear(world.resource::<ClearColor>().to_linear().into())
+                    }
+                    ClearColorConfig::Custom(color) => LoadOp::Clear(color.to_linear().into()),
+                    ClearColorConfig::None => LoadOp::Load,
+                };
+                ImGuiNode::render_target(
+                    context,
+                    *target,
+                    target_context,
+                    &gpu_image.texture_view,
+                    load,
+                    render_context,
+                    world,
+                );
             }
+            return Ok(());
+        };
+
+        let Some(target) = context.views.get(&view) else {
+            return Ok(());
+        };
+        let Some(target_context) = context.targets.get(target) else {
+            return Ok(());
+        };
+        let texture_view = match &target_context.target {
+            ImguiRenderTarget::Window => world
+                .resource::<ExtractedWindows>()
+                .windows
+                .get(target)
+                .and_then(|extracted_window| extracted_window.swap_chain_texture_view.as_ref()),
+            ImguiRenderTarget::Image { image, .. } => world
+                .resource::<RenderAssets<GpuImage>>()
+                .get(*image)
+                .map(|gpu_image| &gpu_image.texture_view),
+        };
+        if let Some(texture_view) = texture_view {
+            ImGuiNode::render_target(
+                context,
+                *target,
+                target_context,
+                texture_view,
+                LoadOp::Load,
+                render_context,
+                world,
+            );
         }
         Ok(())
     }
@@ -289,19 +987,34 @@ fn add_image_to_renderer(
     }
 }
 
-// Update the display scale and reload the font accordingly.
-// This must be performed during Extract as it is the only safe
-// point where we can update the context AND regenerate the font atlas
-fn update_display_scale(
-    previous_display_scale: f32,
-    display_scale: f32,
+// Creates a renderer for a target with the given texture format, which uploads the current font
+// atlas of the given ImGui context. The context must be active.
+fn create_renderer(
     plugin_settings: &ImguiPlugin,
-    context: &ImguiContext,
-    renderer: &mut Renderer,
+    texture_format: TextureFormat,
+    ctx: &mut imgui::Context,
     device: &RenderDevice,
     queue: &RenderQueue,
+) -> Renderer {
+    let mut renderer_config = plugin_settings
+        .color_space
+        .renderer_config(texture_format, plugin_settings.hdr_paper_white_nits);
+    if plugin_settings.alpha8_font_atlas {
+        renderer_config = renderer_config.with_alpha8_font_atlas();
+    }
+    Renderer::new(ctx, device.wgpu_device(), queue, renderer_config)
+}
+
+// Rebuild the font atlas of the given target from the given fonts at the given display scale,
+// which must then be uploaded to the target's renderer. This must be performed during Extract as
+// it is the only safe point where we can update the context AND regenerate the font atlas
+fn rebuild_font_atlas(
+    display_scale: f32,
+    plugin_settings: &ImguiPlugin,
+    fonts: &Arc<ResolvedFonts>,
+    context: &ImguiTargetContext,
+    ctx: &mut imgui::Context,
 ) {
-    let mut ctx = context.ctx.write().unwrap();
     let font_scale = if plugin_settings.apply_display_scale_to_font_size {
         display_scale
     } else {
@@ -314,48 +1027,41 @@ fn update_display_scale(
         1
     };
 
-    let io = ctx.deref_mut().io_mut();
-    io.display_framebuffer_scale = [display_scale, display_scale];
-    io.font_global_scale = 1.0 / font_scale;
+    ctx.io_mut().font_global_scale = 1.0 / font_scale;
 
     // Reload font.
     ctx.fonts().clear();
-    ctx.fonts().add_font(&[FontSource::DefaultFontData {
-        config: Some(imgui::FontConfig {
-            size_pixels: f32::floor(plugin_settings.font_size * font_scale), // Round down to nearest integer, as per https://github.com/ocornut/imgui/blob/master/docs/FAQ.md#q-how-should-i-handle-dpi-in-my-application
-            oversample_h: plugin_settings.font_oversample_h * font_oversample_scale,
-            oversample_v: plugin_settings.font_oversample_v * font_oversample_scale,
-            ..default()
-        }),
-    }]);
-
-    // We have no means of iterating over the textures in Textures, so remove all
-    // of the textures we previously added...
-    for texture_id in context.textures.keys() {
-        renderer.textures.remove(*texture_id);
-    }
-
-    // At this point, the only texture left is the font texture. This function
-    // may update this...
-    renderer.reload_font_texture(ctx.deref_mut(), device.wgpu_device(), queue);
-
-    let mut texture_modify = context.texture_modify.write().unwrap();
+    *context.font_ids.write().unwrap() =
+        fonts.add_to_atlas(ctx.fonts(), font_scale, font_oversample_scale);
+    *context.atlas_fonts.write().unwrap() = Some(fonts.clone());
+}
 
-    // Flag all textures as needing to be re-added
-    for texture_id in context.textures.keys() {
-        texture_modify.to_add.push(*texture_id);
-    }
+// Update the display scale and rebuild the font atlas accordingly, which must then be uploaded to
+// the target's renderer. This must be performed during Extract as it is the only safe
+// point where we can update the context AND regenerate the font atlas
+fn update_display_scale(
+    display_scale: f32,
+    plugin_settings: &ImguiPlugin,
+    fonts: &Arc<ResolvedFonts>,
+    context: &ImguiTargetContext,
+) {
+    context.activate();
+    let mut ctx = context.ctx.write().unwrap();
+    ctx.io_mut().display_framebuffer_scale = [display_scale, display_scale];
 
-    // The only new texture that may have been created is the font texture, so the next
-    // free id is either the current free id, or one past the font texture
-    let font_texture_id = ctx.fonts().tex_id;
-    let next = &mut texture_modify.next_free_id;
-    *next = usize::max(font_texture_id.id() + 1, *next);
+    rebuild_font_atlas(
+        display_scale,
+        plugin_settings,
+        fonts,
+        context,
+        ctx.deref_mut(),
+    );
 
     // Update style for DPI change, as per:
     // https://github.com/ocornut/imgui/blob/master/docs/FAQ.md#q-how-should-i-handle-dpi-in-my-application
     ctx.style_mut()
-        .scale_all_sizes(display_scale / previous_display_scale);
+        .scale_all_sizes(display_scale / context.style_scale.get());
+    context.style_scale.set(display_scale);
 }
 
 /// Configuration settings for this plugin
@@ -365,9 +1071,29 @@ pub struct ImguiPlugin {
     /// Pass None to disable automatic .Ini saving
     pub ini_filename: Option<PathBuf>,
 
-    /// The unscaled font size to use (default is 13).
+    /// Where the ini settings of each ImGui context are loaded from and saved to
+    /// (default is `ImguiFileSettingsStorage`, which uses the ini filename as a file path).
+    pub settings_storage: Arc<dyn ImguiSettingsStorage>,
+
+    /// How long to wait after the ini settings change before saving them (default is 5 seconds).
+    pub settings_autosave_interval: Duration,
+
+    /// The unscaled font size to use for ImGui's built-in font when `fonts` is empty, or until
+    /// the configured fonts have loaded (default is 13).
     pub font_size: f32,
 
+    /// The fonts to add to ImGui's font atlas, the first of which is the default font (default is
+    /// empty, which uses ImGui's built-in font). The atlas is rebuilt at the display scale of each
+    /// window or image target once every font's data is available.
+    ///
+    /// The font settings of this plugin form the initial `ImguiFontAtlasConfig`, which can be
+    /// changed at runtime with `ImguiContext::request_font_rebuild`.
+    ///
+    /// Fonts with `ImguiGlyphRanges::Registered` only contain the glyphs of text registered with
+    /// `ImguiContext::register_glyphs`. Text passed to widgets is not registered automatically,
+    /// so register every string outside of Latin-1 before drawing it.
+    pub fonts: Vec<ImguiFont>,
+
     /// The number of horizontal font samples to perform. Must be >= 1 (default is 1).
     pub font_oversample_h: i32,
 
@@ -379,17 +1105,229 @@ pub struct ImguiPlugin {
 
     /// Whether to apply the window display scale to the number of font samples (default is true).
     pub apply_display_scale_to_font_oversample: bool,
+
+    /// The style applied to each ImGui context (default is `ImguiStylePreset::Dark`).
+    /// This can be changed at runtime with `ImguiContext::set_style`.
+    pub style: ImguiStyle,
+
+    /// Whether to store the font atlas as a single channel (Alpha8) texture, which uses a quarter
+    /// of the memory of the default RGBA texture (default is false).
+    pub alpha8_font_atlas: bool,
+
+    /// The colour space that ImGui's fragment shader outputs (default is `ImguiColorSpace::Auto`).
+    pub color_space: ImguiColorSpace,
+
+    /// The brightness in nits that ImGui's white is displayed at on HDR targets (default is 80,
+    /// the reference white of scRGB, which leaves colours unscaled).
+    pub hdr_paper_white_nits: f32,
+
+    /// Which gamepad drives ImGui's gamepad navigation (default is `ImguiGamepad::None`).
+    /// This can be changed at runtime with `ImguiContext::set_gamepad`.
+    pub gamepad: ImguiGamepad,
+
+    /// Whether to reset `ButtonInput<KeyCode>` and `ButtonInput<MouseButton>` while ImGui wants
+    /// to capture the keyboard or mouse respectively, so that game systems do not react to input
+    /// consumed by ImGui (default is false). See `ImguiInputCapture`.
+    pub clear_captured_input: bool,
+
+    /// Whether to enable docking in each ImGui context (default is false). Requires the `docking`
+    /// cargo feature. See `imgui_dockspace_over_window`.
+    #[cfg(feature = "docking")]
+    pub docking: bool,
+
+    /// Whether ImGui windows can be dragged outside of the primary window and any `ImguiWindow`,
+    /// in which case they become Bevy windows of their own (default is false). Requires the
+    /// `docking` cargo feature. See `ImguiViewportWindow`.
+    ///
+    /// Viewport windows use the display scale of the window that owns them, and Bevy does not
+    /// report whether windows are minimized, so minimized viewport windows are still rendered.
+    #[cfg(feature = "docking")]
+    pub viewports: bool,
+
+    /// The clipboard used by ImGui's text widgets (default is `ImguiClipboard::default()`).
+    pub clipboard: ImguiClipboard,
+
+    /// The number of lines to scroll per pixel of mouse wheel movement, for devices such as
+    /// trackpads that report scrolling in pixels (default is 0.05).
+    pub mouse_wheel_lines_per_pixel: f32,
+
+    /// How touches are translated into ImGui mouse input.
+    pub touch: ImguiTouch,
+}
+
+/// Configures how touches on touchscreen devices are translated into ImGui mouse input.
+///
+/// A single touch moves the mouse, and presses the left button once it is dragged or released.
+/// The bundled version of Dear ImGui does not distinguish touchscreens from mice, so ImGui
+/// treats touches exactly as mouse input.
+#[derive(Clone, Debug)]
+pub struct ImguiTouch {
+    /// Whether touches drive ImGui's mouse input (default is true).
+    pub enabled: bool,
+
+    /// How long a touch must be held in place to emulate a right click.
+    /// Pass None to disable long presses (default is 500ms).
+    pub long_press_duration: Option<Duration>,
+
+    /// How far in logical pixels a touch can move before it is no longer considered to be
+    /// held in place (default is 10).
+    pub long_press_tolerance: f32,
+
+    /// Whether dragging two touches emulates the mouse wheel, using
+    /// `ImguiPlugin::mouse_wheel_lines_per_pixel` (default is true).
+    pub two_finger_scroll: bool,
+}
+
+impl Default for ImguiTouch {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            long_press_duration: Some(Duration::from_millis(500)),
+            long_press_tolerance: 10.0,
+            two_finger_scroll: true,
+        }
+    }
+}
+
+/// System sets for ordering systems relative to the start and end of each imgui frame.
+///
+/// UI can be submitted by any system that runs after `ImguiSet::NewFrame` and before
+/// `ImguiSet::EndFrame`, which includes every system in `Update` and `PostUpdate`.
+#[derive(SystemSet, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImguiSet {
+    /// Starts a frame for each ImGui context, in `PreUpdate` after Bevy's input is processed.
+    NewFrame,
+
+    /// Ends the frame of each ImGui context, in `Last`.
+    EndFrame,
+}
+
+/// Whether ImGui wants to capture input, updated at the start of each frame.
+///
+/// Game systems can use this resource, or the `imgui_wants_mouse`, `imgui_wants_keyboard` and
+/// `imgui_wants_text_input` run conditions, to ignore input that is being consumed by ImGui.
+/// Alternatively, set `ImguiPlugin::clear_captured_input` to hide captured input from game systems.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImguiInputCapture {
+    /// Whether any ImGui window context wants to capture the mouse.
+    pub mouse: bool,
+
+    /// Whether any ImGui window context wants to capture the keyboard.
+    pub keyboard: bool,
+
+    /// Whether any ImGui window context wants text input (e.g. a text field is active).
+    pub text_input: bool,
+}
+
+/// A run condition that is true while ImGui wants to capture the mouse.
+pub fn imgui_wants_mouse(capture: Res<ImguiInputCapture>) -> bool {
+    capture.mouse
+}
+
+/// A run condition that is true while ImGui wants to capture the keyboard.
+pub fn imgui_wants_keyboard(capture: Res<ImguiInputCapture>) -> bool {
+    capture.keyboard
+}
+
+/// A run condition that is true while ImGui wants text input.
+pub fn imgui_wants_text_input(capture: Res<ImguiInputCapture>) -> bool {
+    capture.text_input
+}
+
+/// Selects the gamepad that drives ImGui's gamepad navigation.
+///
+/// When a gamepad is selected, `ConfigFlags::NAV_ENABLE_GAMEPAD` is set on each ImGui context,
+/// and the gamepad's input is routed to the focused window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImguiGamepad {
+    /// Gamepad navigation is disabled.
+    #[default]
+    None,
+
+    /// The first connected gamepad drives the UI.
+    First,
+
+    /// The given gamepad entity drives the UI.
+    Entity(Entity),
+}
+
+/// The colour space that ImGui's fragment shader outputs.
+///
+/// ImGui style colours are specified in sRGB. Render targets with an sRGB texture format
+/// expect linear colour output (which the GPU then encodes to sRGB), whereas other formats
+/// expect sRGB colour output as-is. Render targets with a floating-point texture format are
+/// treated as HDR, and expect linear colour output scaled to the desired brightness.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImguiColorSpace {
+    /// Choose the colour space from the texture format of each window or image target.
+    #[default]
+    Auto,
+
+    /// Always convert colours to linear, for render targets with an sRGB texture format.
+    Linear,
+
+    /// Always output sRGB colours unchanged, for render targets with a non-sRGB texture format.
+    Srgb,
+
+    /// Always convert colours to linear, scaled by `ImguiPlugin::hdr_paper_white_nits`,
+    /// for HDR render targets.
+    Hdr,
+}
+
+impl ImguiColorSpace {
+    // Returns the renderer configuration that outputs this colour space to the given texture format
+    fn renderer_config(
+        self,
+        texture_format: TextureFormat,
+        hdr_paper_white_nits: f32,
+    ) -> RendererConfig<'static> {
+        let color_space = match self {
+            ImguiColorSpace::Auto => match texture_format {
+                TextureFormat::Rgba16Float
+                | TextureFormat::Rgba32Float
+                | TextureFormat::Rg11b10Ufloat => ImguiColorSpace::Hdr,
+                format if format.is_srgb() => ImguiColorSpace::Linear,
+                _ => ImguiColorSpace::Srgb,
+            },
+            color_space => color_space,
+        };
+        let renderer_config = match color_space {
+            ImguiColorSpace::Hdr => RendererConfig::new_hdr(hdr_paper_white_nits),
+            ImguiColorSpace::Srgb => RendererConfig::new_srgb(),
+            _ => RendererConfig::new(),
+        };
+        RendererConfig {
+            texture_format,
+            ..renderer_config
+        }
+    }
 }
 
 impl Default for ImguiPlugin {
     fn default() -> Self {
         Self {
             ini_filename: Default::default(),
+            settings_storage: Arc::new(ImguiFileSettingsStorage),
+            settings_autosave_interval: Duration::from_secs(5),
             font_size: 13.0,
+            fonts: Vec::new(),
             font_oversample_h: 1,
             font_oversample_v: 1,
             apply_display_scale_to_font_size: true,
             apply_display_scale_to_font_oversample: true,
+            style: ImguiStyle::default(),
+            alpha8_font_atlas: false,
+            color_space: ImguiColorSpace::Auto,
+            hdr_paper_white_nits: imgui_wgpu_rs_local::SCRGB_REFERENCE_WHITE_NITS,
+            gamepad: ImguiGamepad::None,
+            clear_captured_input: false,
+            #[cfg(feature = "docking")]
+            docking: false,
+            #[cfg(feature = "docking")]
+            viewports: false,
+            clipboard: ImguiClipboard::default(),
+            mouse_wheel_lines_per_pixel: 0.05,
+            touch: ImguiTouch::default(),
         }
     }
 }
@@ -398,59 +1336,44 @@ impl Plugin for ImguiPlugin {
     fn build(&self, _app: &mut App) {}
 
     fn finish(&self, app: &mut App) {
-        let mut ctx = imgui::Context::create();
-        ctx.set_ini_filename(self.ini_filename.clone());
-
-        for key_index in 0..imgui::Key::COUNT {
-            ctx.io_mut()[imgui::Key::VARIANTS[key_index]] = key_index as _;
-        }
-
-        let display_scale = {
-            let mut system_state: SystemState<Query<&Window, With<PrimaryWindow>>> =
-                SystemState::new(app.world_mut());
-            let primary_window = system_state.get(app.world());
-            primary_window.single().unwrap().scale_factor()
+        let font_atlas_config = ImguiFontAtlasConfig {
+            fonts: self.fonts.clone(),
+            font_size: self.font_size,
+            font_oversample_h: self.font_oversample_h,
+            font_oversample_v: self.font_oversample_v,
         };
-
-        let mut context = ImguiContext {
-            ctx: RwLock::new(ctx),
-            ui: None,
+        let context = ImguiContext {
+            targets: HashMap::new(),
+            primary_window: None,
+            plugin: self.clone(),
             textures: HashMap::new(),
-            texture_modify: default(),
-            rendered_draw_data: default(),
+            // Texture id 0 is reserved for the font atlas of each ImGui context
+            texture_modify: RwLock::new(ImguiTextureModifyState {
+                next_free_id: 1,
+                ..default()
+            }),
+            draw_callbacks: HashMap::new(),
+            next_draw_callback_id: 0,
+            font_atlas_config: font_atlas_config.clone(),
+            font_atlas_config_changed: true,
+            registered_glyphs: default(),
+            // Use the built-in font until the configured fonts are available
+            fonts: Arc::new(ResolvedFonts::fallback(&font_atlas_config)),
+            font_generation: 0,
+            font_rebuild_requested: false,
+            pending_rebuilt_font_generation: None,
+            style: self.style.clone(),
+            style_asset: None,
+            style_asset_changed: false,
+            // Apply the style to each target when it is created
+            style_generation: 1,
+            pending_settings_handlers: Vec::new(),
         };
 
         if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
-            let mut system_state: SystemState<(Res<RenderDevice>, Res<RenderQueue>)> =
-                SystemState::new(render_app.world_mut());
-            let (device, queue) = system_state.get_mut(render_app.world_mut());
-
-            // Here we create a new ImGui renderer with a default format. At this point,
-            // we don't know what format the window surface is going to be set up with,
-            // and yet we need to initialise the renderer so that the texture glyphs
-            // are created before new_frame is called on the imgui context.
-            //
-            // This will give us a functonal imgui context. If, at the point at which we
-            // extract the scene, we realise that the window has an incompatible
-            // format, the renderer will be recreated with a compatible format.
-            let renderer_config = RendererConfig::default();
-            let texture_format = renderer_config.texture_format;
-            let mut renderer = Renderer::new(
-                context.ctx.get_mut().unwrap(),
-                device.wgpu_device(),
-                &queue,
-                renderer_config,
-            );
-            update_display_scale(
-                1.0,
-                display_scale,
-                self,
-                &context,
-                &mut renderer,
-                &device,
-                &queue,
-            );
-
+            // Renderers for each ImGui context are created during Extract, at which point
+            // we know what format the window surface has been set up with. Until then, each
+            // context is given a default font atlas so that frames can be started.
             render_app.add_render_graph_node::<ImGuiNode>(Core2d, ImGuiNodeLabel);
 
             render_app.add_render_graph_edges(Core2d, (Node2d::EndMainPass, ImGuiNodeLabel));
@@ -473,14 +1396,16 @@ impl Plugin for ImguiPlugin {
 
             render_app.add_render_graph_edges(Core3d, (Node3d::Upscaling, ImGuiNodeLabel));
 
+            // Image targets that no camera renders to are drawn before any cameras are rendered
+            let mut render_graph = render_app.world_mut().resource_mut::<RenderGraph>();
+            render_graph.add_node(ImGuiNodeLabel, ImGuiNode);
+            render_graph.add_node_edge(ImGuiNodeLabel, CameraDriverLabel);
+
             render_app.insert_resource(ImguiRenderContext {
-                renderer: RwLock::new(renderer),
-                texture_format,
-                draw: OwnedDrawDataWrap::default(),
+                targets: HashMap::new(),
                 plugin: self.clone(),
-                display_scale,
-                textures_to_add: HashMap::new(),
-                textures_to_remove: Vec::new(),
+                views: HashMap::new(),
+                draw_callbacks: HashMap::new(),
             });
 
             render_app.world_mut().insert_non_send_resource(NonSendHack);
@@ -488,353 +1413,716 @@ impl Plugin for ImguiPlugin {
             render_app.add_systems(ExtractSchedule, imgui_extract_frame_system);
             render_app.add_systems(
                 Render,
-                imgui_update_textures_system.in_set(RenderSet::Prepare),
+                (imgui_update_textures_system, imgui_prepare_views_system)
+                    .in_set(RenderSet::Prepare),
             );
         } else {
             return;
         }
 
         app.insert_non_send_resource(context);
+        app.init_resource::<ImguiInputCapture>();
+
+        app.init_asset::<ImguiStyle>();
+        #[cfg(feature = "style_asset")]
+        app.init_asset_loader::<style::ImguiStyleLoader>();
+
+        app.configure_sets(PreUpdate, ImguiSet::NewFrame.after(InputSystem));
+        app.configure_sets(Last, ImguiSet::EndFrame);
+
+        app.add_systems(PreUpdate, imgui_new_frame_system.in_set(ImguiSet::NewFrame));
+        app.add_systems(
+            PreUpdate,
+            imgui_update_style_asset_system
+                .in_set(ImguiSet::NewFrame)
+                .before(imgui_new_frame_system),
+        );
+        app.add_event::<ImguiFontAtlasRebuilt>();
+        app.add_systems(
+            PreUpdate,
+            (imgui_resolve_fonts_system, imgui_font_atlas_rebuilt_system),
+        );
+        app.add_systems(Last, imgui_end_frame_system.in_set(ImguiSet::EndFrame));
+
+        app.init_resource::<ImguiUiQueue>();
+        app.add_systems(
+            Last,
+            ui::imgui_submit_queued_ui_system
+                .in_set(ImguiSet::EndFrame)
+                .before(imgui_end_frame_system),
+        );
+
+        #[cfg(feature = "docking")]
+        app.add_systems(
+            PreUpdate,
+            viewports::imgui_update_viewports_system
+                .in_set(ImguiSet::NewFrame)
+                .before(imgui_new_frame_system),
+        );
 
-        app.add_systems(PreUpdate, imgui_new_frame_system);
-        app.add_systems(Last, imgui_end_frame_system);
+        #[cfg(feature = "cursor")]
+        app.add_systems(
+            Last,
+            cursor::imgui_update_cursor_system
+                .in_set(ImguiSet::EndFrame)
+                .after(ui::imgui_submit_queued_ui_system)
+                .before(imgui_end_frame_system),
+        );
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn imgui_new_frame_system(
     mut context: NonSendMut<ImguiContext>,
-    primary_window: Query<(Entity, &Window), With<PrimaryWindow>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mouse: Res<ButtonInput<bevy::input::mouse::MouseButton>>,
-    mut received_chars: EventReader<KeyboardInput>,
+    mut windows: Query<
+        (
+            Entity,
+            &mut Window,
+            Option<&ImguiWindow>,
+            Has<PrimaryWindow>,
+        ),
+        Or<(With<PrimaryWindow>, With<ImguiWindow>)>,
+    >,
+    #[cfg(feature = "docking")] viewport_windows: Query<
+        (Entity, &Window, &ImguiViewportWindow),
+        (Without<PrimaryWindow>, Without<ImguiWindow>),
+    >,
+    image_targets: Query<(Entity, &ImguiImageTarget)>,
+    images: Res<Assets<Image>>,
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut ime_events: EventReader<Ime>,
+    mut mouse_button_events: EventReader<MouseButtonInput>,
     mut mouse_wheel: EventReader<bevy::input::mouse::MouseWheel>,
+    mut touch_events: EventReader<TouchInput>,
+    time: Res<Time<Real>>,
+    gamepads: Query<(Entity, &Gamepad)>,
+    mut input_capture: ResMut<ImguiInputCapture>,
 ) {
-    const UNKNOWN_KEYCODE: KeyCode = KeyCode::F35;
-    const IMGUI_TO_BEVY_KEYS: [bevy::input::keyboard::KeyCode; imgui::Key::COUNT] = [
-        KeyCode::Tab,
-        KeyCode::ArrowLeft,
-        KeyCode::ArrowRight,
-        KeyCode::ArrowUp,
-        KeyCode::ArrowDown,
-        KeyCode::PageUp,
-        KeyCode::PageDown,
-        KeyCode::Home,
-        KeyCode::End,
-        KeyCode::Insert,
-        KeyCode::Delete,
-        KeyCode::Backspace,
-        KeyCode::Space,
-        KeyCode::Enter,
-        KeyCode::Escape,
-        KeyCode::ControlLeft,
-        KeyCode::ShiftLeft,
-        KeyCode::AltLeft,
-        KeyCode::SuperLeft,
-        KeyCode::ControlRight,
-        KeyCode::ShiftRight,
-        KeyCode::AltRight,
-        KeyCode::SuperRight,
-        KeyCode::ContextMenu, // sys::ImGuiKey_Menu
-        KeyCode::Digit0,
-        KeyCode::Digit1,
-        KeyCode::Digit2,
-        KeyCode::Digit3,
-        KeyCode::Digit4,
-        KeyCode::Digit5,
-        KeyCode::Digit6,
-        KeyCode::Digit7,
-        KeyCode::Digit8,
-        KeyCode::Digit9,
-        KeyCode::KeyA,
-        KeyCode::KeyB,
-        KeyCode::KeyC,
-        KeyCode::KeyD,
-        KeyCode::KeyE,
-        KeyCode::KeyF,
-        KeyCode::KeyG,
-        KeyCode::KeyH,
-        KeyCode::KeyI,
-        KeyCode::KeyJ,
-        KeyCode::KeyK,
-        KeyCode::KeyL,
-        KeyCode::KeyM,
-        KeyCode::KeyN,
-        KeyCode::KeyO,
-        KeyCode::KeyP,
-        KeyCode::KeyQ,
-        KeyCode::KeyR,
-        KeyCode::KeyS,
-        KeyCode::KeyT,
-        KeyCode::KeyU,
-        KeyCode::KeyV,
-        KeyCode::KeyW,
-        KeyCode::KeyX,
-        KeyCode::KeyY,
-        KeyCode::KeyZ,
-        KeyCode::F1,
-        KeyCode::F2,
-        KeyCode::F3,
-        KeyCode::F4,
-        KeyCode::F5,
-        KeyCode::F6,
-        KeyCode::F7,
-        KeyCode::F8,
-        KeyCode::F9,
-        KeyCode::F10,
-        KeyCode::F11,
-        KeyCode::F12,
-        KeyCode::Quote,
-        KeyCode::Comma,
-        KeyCode::Minus,
-        KeyCode::Period,
-        KeyCode::Slash,
-        KeyCode::Semicolon,
-        KeyCode::Equal,
-        KeyCode::BracketLeft,
-        KeyCode::Backslash,
-        KeyCode::BracketRight,
-        KeyCode::Backquote,
-        KeyCode::CapsLock,
-        KeyCode::ScrollLock,
-        KeyCode::NumLock,
-        KeyCode::PrintScreen,
-        KeyCode::Pause,
-        KeyCode::Numpad0,
-        KeyCode::Numpad1,
-        KeyCode::Numpad2,
-        KeyCode::Numpad3,
-        KeyCode::Numpad4,
-        KeyCode::Numpad5,
-        KeyCode::Numpad6,
-        KeyCode::Numpad7,
-        KeyCode::Numpad8,
-        KeyCode::Numpad9,
-        KeyCode::NumpadDecimal,
-        KeyCode::NumpadDivide,
-        KeyCode::NumpadMultiply,
-        KeyCode::NumpadSubtract,
-        KeyCode::NumpadAdd,
-        KeyCode::NumpadEnter,
-        KeyCode::NumpadEqual,
-        UNKNOWN_KEYCODE, // GamepadStart = sys::ImGuiKey_GamepadStart,
-        UNKNOWN_KEYCODE, // GamepadBack = sys::ImGuiKey_GamepadBack,
-        UNKNOWN_KEYCODE, // GamepadFaceLeft = sys::ImGuiKey_GamepadFaceLeft,
-        UNKNOWN_KEYCODE, // GamepadFaceRight = sys::ImGuiKey_GamepadFaceRight,
-        UNKNOWN_KEYCODE, // GamepadFaceUp = sys::ImGuiKey_GamepadFaceUp,
-        UNKNOWN_KEYCODE, // GamepadFaceDown = sys::ImGuiKey_GamepadFaceDown,
-        UNKNOWN_KEYCODE, // GamepadDpadLeft = sys::ImGuiKey_GamepadDpadLeft,
-        UNKNOWN_KEYCODE, // GamepadDpadRight = sys::ImGuiKey_GamepadDpadRight,
-        UNKNOWN_KEYCODE, // GamepadDpadUp = sys::ImGuiKey_GamepadDpadUp,
-        UNKNOWN_KEYCODE, // GamepadDpadDown = sys::ImGuiKey_GamepadDpadDown,
-        UNKNOWN_KEYCODE, // GamepadL1 = sys::ImGuiKey_GamepadL1,
-        UNKNOWN_KEYCODE, // GamepadR1 = sys::ImGuiKey_GamepadR1,
-        UNKNOWN_KEYCODE, // GamepadL2 = sys::ImGuiKey_GamepadL2,
-        UNKNOWN_KEYCODE, // GamepadR2 = sys::ImGuiKey_GamepadR2,
-        UNKNOWN_KEYCODE, // GamepadL3 = sys::ImGuiKey_GamepadL3,
-        UNKNOWN_KEYCODE, // GamepadR3 = sys::ImGuiKey_GamepadR3,
-        UNKNOWN_KEYCODE, // GamepadLStickLeft = sys::ImGuiKey_GamepadLStickLeft,
-        UNKNOWN_KEYCODE, // GamepadLStickRight = sys::ImGuiKey_GamepadLStickRight,
-        UNKNOWN_KEYCODE, // GamepadLStickUp = sys::ImGuiKey_GamepadLStickUp,
-        UNKNOWN_KEYCODE, // GamepadLStickDown = sys::ImGuiKey_GamepadLStickDown,
-        UNKNOWN_KEYCODE, // GamepadRStickLeft = sys::ImGuiKey_GamepadRStickLeft,
-        UNKNOWN_KEYCODE, // GamepadRStickRight = sys::ImGuiKey_GamepadRStickRight,
-        UNKNOWN_KEYCODE, // GamepadRStickUp = sys::ImGuiKey_GamepadRStickUp,
-        UNKNOWN_KEYCODE, // GamepadRStickDown = sys::ImGuiKey_GamepadRStickDown,
-        UNKNOWN_KEYCODE, // MouseLeft = sys::ImGuiKey_MouseLeft,
-        UNKNOWN_KEYCODE, // MouseRight = sys::ImGuiKey_MouseRight,
-        UNKNOWN_KEYCODE, // MouseMiddle = sys::ImGuiKey_MouseMiddle,
-        UNKNOWN_KEYCODE, // MouseX1 = sys::ImGuiKey_MouseX1,
-        UNKNOWN_KEYCODE, // MouseX2 = sys::ImGuiKey_MouseX2,
-        UNKNOWN_KEYCODE, // MouseWheelX = sys::ImGuiKey_MouseWheelX,
-        UNKNOWN_KEYCODE, // MouseWheelY = sys::ImGuiKey_MouseWheelY,
-        UNKNOWN_KEYCODE, // ReservedForModCtrl = sys::ImGuiKey_ReservedForModCtrl,
-        UNKNOWN_KEYCODE, // ReservedForModShift = sys::ImGuiKey_ReservedForModShift,
-        UNKNOWN_KEYCODE, // ReservedForModAlt = sys::ImGuiKey_ReservedForModAlt,
-        UNKNOWN_KEYCODE, // ReservedForModSuper = sys::ImGuiKey_ReservedForModSuper
-    ];
-
-    let ui_ptr: NonNull<imgui::Ui>;
-    {
-        let ctx = context.ctx.get_mut().unwrap();
-        let io = ctx.io_mut();
+    let context = context.as_mut();
+
+    let gamepad = match context.plugin.gamepad {
+        ImguiGamepad::None => None,
+        ImguiGamepad::First => gamepads.iter().next().map(|(_, gamepad)| gamepad),
+        ImguiGamepad::Entity(entity) => gamepads.get(entity).ok().map(|(_, gamepad)| gamepad),
+    };
+    let gamepad_enabled = context.plugin.gamepad != ImguiGamepad::None;
+
+    let mut capture = ImguiInputCapture::default();
+
+    // Destroy the contexts of windows that have been closed, or targets that are no longer marked
+    context
+        .targets
+        .retain(|entity, _| windows.contains(*entity) || image_targets.contains(*entity));
+    context.primary_window = None;
 
-        if let Ok((_, primary)) = primary_window.single() {
-            io.display_size = [primary.width(), primary.height()];
-            io.display_framebuffer_scale = [primary.scale_factor(), primary.scale_factor()];
+    let keyboard_events: Vec<_> = keyboard_events.read().collect();
+    let ime_events: Vec<_> = ime_events.read().collect();
 
-            if let Some(pos) = primary.cursor_position() {
-                io.mouse_pos = [pos.x, pos.y];
+    // Make sure that fonts with registered glyph ranges can display any text that is typed
+    for e in keyboard_events.iter() {
+        if let Some(text) = &e.text {
+            context.register_glyphs(text);
+        }
+    }
+    for e in ime_events.iter() {
+        if let Ime::Commit { value, .. } = e {
+            context.register_glyphs(value);
+        }
+    }
+    // Input to the windows of ImGui viewports is routed to the context of the owning window
+    #[cfg(feature = "docking")]
+    let viewport_input = viewports::viewport_input(context, viewport_windows.iter());
+    #[cfg(not(feature = "docking"))]
+    let viewport_input = input::ViewportInput::default();
+
+    let mouse_button_events: Vec<_> = mouse_button_events.read().collect();
+    let mouse_wheel: Vec<_> = mouse_wheel.read().collect();
+    let touch_events: Vec<_> = touch_events
+        .read()
+        .filter(|_| context.plugin.touch.enabled)
+        .collect();
+
+    for (entity, mut window, imgui_window, is_primary) in &mut windows {
+        if is_primary {
+            context.primary_window = Some(entity);
+        }
+
+        let target_context = context.targets.entry(entity).or_insert_with(|| {
+            let ini_filename = if is_primary {
+                context.plugin.ini_filename.clone()
+            } else {
+                imgui_window.and_then(|imgui_window| imgui_window.ini_filename.clone())
+            };
+            Box::new(ImguiTargetContext::new(ini_filename, &context.plugin))
+        });
+
+        target_context.activate();
+        #[cfg(feature = "docking")]
+        if context.plugin.viewports && target_context.viewports.is_none() {
+            target_context.viewports = Some(viewports::enable_viewports(
+                target_context.ctx.get_mut().unwrap(),
+                &window,
+            ));
+        }
+
+        // While viewports are enabled, positions are relative to the desktop rather than the window
+        #[cfg(feature = "docking")]
+        let origin = match target_context.viewports {
+            Some(ref viewports) => viewports.borrow().owner_position(),
+            None => Vec2::ZERO,
+        };
+        #[cfg(not(feature = "docking"))]
+        let origin = Vec2::ZERO;
+
+        if target_context.style_generation != context.style_generation {
+            target_context.apply_style(&context.style, context.style_generation);
+        }
+        for handler in
+            take_settings_handlers(&mut context.pending_settings_handlers, entity, is_primary)
+        {
+            target_context.add_settings_handler(handler);
+        }
+
+        // Gamepad state is only routed to the focused window (or one of its viewport windows)
+        let focused = window.focused || viewport_input.focused.contains(&entity);
+
+        // Losing focus releases all keys that are held in ImGui
+        if focused != target_context.focused {
+            unsafe { imgui::sys::ImGuiIO_AddFocusEvent(imgui::sys::igGetIO(), focused) };
+            target_context.focused = focused;
+            target_context.held_keys.clear();
+        }
+
+        // Enable IME while ImGui wants text input (as of the previous frame), leaving it
+        // to the game otherwise, and show the candidate window below the text cursor
+        let want_text_input = target_context.ctx.get_mut().unwrap().io().want_text_input;
+        if want_text_input != target_context.want_text_input {
+            window.ime_enabled = want_text_input;
+            target_context.want_text_input = want_text_input;
+        }
+        let platform_ime_data = target_context.platform_ime_data.get();
+        if platform_ime_data.want_visible {
+            let ime_position = Vec2::new(
+                platform_ime_data.input_pos[0],
+                platform_ime_data.input_pos[1] + platform_ime_data.input_line_height,
+            ) - origin;
+            if window.ime_position != ime_position {
+                window.ime_position = ime_position;
             }
         }
 
-        io.mouse_down[0] = mouse.pressed(bevy::input::mouse::MouseButton::Left);
-        io.mouse_down[1] = mouse.pressed(bevy::input::mouse::MouseButton::Right);
-        io.mouse_down[2] = mouse.pressed(bevy::input::mouse::MouseButton::Middle);
+        let ctx = target_context.ctx.get_mut().unwrap();
+        let io = ctx.io_mut();
 
-        for e in received_chars.read() {
+        io.display_size = [window.width(), window.height()];
+        io.display_framebuffer_scale = [window.scale_factor(), window.scale_factor()];
+
+        // -f32::MAX is Dear ImGui's convention for the mouse being unavailable.
+        // Active touches drive the mouse position instead of the cursor.
+        if !target_context.touch.is_active() {
+            io.add_mouse_pos_event(
+                window
+                    .cursor_position()
+                    .map(|pos| pos + origin)
+                    .or(viewport_input.cursor_positions.get(&entity).copied())
+                    .map_or([-f32::MAX, -f32::MAX], |pos| [pos.x, pos.y]),
+            );
+        }
+        let window_touch_events: Vec<_> = touch_events
+            .iter()
+            .filter(|e| viewport_input.target(e.window) == entity)
+            .map(|e| {
+                let origin = if e.window == entity {
+                    origin
+                } else {
+                    viewport_input.origin(e.window)
+                };
+                TouchInput {
+                    position: e.position + origin,
+                    ..**e
+                }
+            })
+            .collect();
+        target_context.touch.update(
+            io,
+            window_touch_events.iter(),
+            &context.plugin.touch,
+            time.elapsed(),
+            context.plugin.mouse_wheel_lines_per_pixel,
+        );
+
+        // Mouse buttons are driven by events rather than `ButtonInput<MouseButton>`, which
+        // may be reset while ImGui is capturing the mouse
+        for e in mouse_button_events
+            .iter()
+            .filter(|e| viewport_input.target(e.window) == entity)
+        {
+            input::add_mouse_button_event(io, e);
+        }
+
+        for e in keyboard_events
+            .iter()
+            .filter(|e| viewport_input.target(e.window) == entity)
+        {
+            input::add_keyboard_event(io, e, &mut target_context.held_keys);
+
+            // Text is absent when the keypress was consumed by an IME
             if e.state == ButtonState::Pressed {
-                match &e.logical_key {
-                    Key::Character(c) => {
-                        io.add_input_character(c.chars().last().unwrap());
-                    }
-                    Key::Dead(Some(c)) => {
-                        io.add_input_character(*c);
-                    }
-                    Key::Space => {
-                        io.add_input_character(' ');
-                    }
-                    _ => {}
+                if let Some(text) = &e.text {
+                    input::add_input_text(io, text);
                 }
             }
         }
 
-        for (key_index, key) in IMGUI_TO_BEVY_KEYS.iter().enumerate() {
-            io.keys_down[key_index] = keyboard.pressed(*key);
+        // Text composed with an IME is received once composition is complete
+        for e in ime_events.iter() {
+            if let Ime::Commit {
+                window: ime_window,
+                value,
+            } = e
+            {
+                if viewport_input.target(*ime_window) == entity {
+                    input::add_input_text(io, value);
+                }
+            }
         }
 
-        io.key_alt = keyboard.pressed(KeyCode::AltLeft) || keyboard.pressed(KeyCode::AltRight);
-        io.key_ctrl =
-            keyboard.pressed(KeyCode::ControlLeft) || keyboard.pressed(KeyCode::ControlRight);
-        io.key_shift =
-            keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight);
-        io.key_super =
-            keyboard.pressed(KeyCode::SuperLeft) || keyboard.pressed(KeyCode::SuperRight);
+        io.config_flags
+            .set(imgui::ConfigFlags::NAV_ENABLE_GAMEPAD, gamepad_enabled);
+        input::add_gamepad_events(io, gamepad.filter(|_| focused));
+
+        input::add_mouse_wheel_events(
+            io,
+            mouse_wheel
+                .iter()
+                .copied()
+                .filter(|e| viewport_input.target(e.window) == entity),
+            context.plugin.mouse_wheel_lines_per_pixel,
+        );
+        let ui_ptr = unsafe { NonNull::new_unchecked(ctx.new_frame()) };
+        target_context.ui = Some(ui_ptr);
+
+        // ImGui decides whether it wants to capture input when starting a frame
+        let io = ctx.io();
+        capture.mouse |= io.want_capture_mouse;
+        capture.keyboard |= io.want_capture_keyboard;
+        capture.text_input |= io.want_text_input;
+    }
+
+    // Only trigger change detection when the capture state actually changes
+    input_capture.set_if_neq(capture);
 
-        for e in mouse_wheel.read() {
-            io.mouse_wheel = e.y;
-            io.mouse_wheel_h = e.x;
+    if context.plugin.clear_captured_input {
+        if capture.mouse {
+            mouse.reset_all();
+        }
+        if capture.keyboard {
+            keyboard.reset_all();
         }
-        ui_ptr = unsafe { NonNull::new_unchecked(ctx.new_frame()) };
     }
-    context.ui = Some(ui_ptr);
+
+    for (entity, image_target) in &image_targets {
+        // Wait until the image is loaded before starting frames for it
+        let Some(image) = images.get(&image_target.image) else {
+            continue;
+        };
+
+        let target_context = context.targets.entry(entity).or_insert_with(|| {
+            Box::new(ImguiTargetContext::new(
+                image_target.ini_filename.clone(),
+                &context.plugin,
+            ))
+        });
+
+        target_context.activate();
+        if target_context.style_generation != context.style_generation {
+            target_context.apply_style(&context.style, context.style_generation);
+        }
+        for handler in take_settings_handlers(&mut context.pending_settings_handlers, entity, false)
+        {
+            target_context.add_settings_handler(handler);
+        }
+        let ctx = target_context.ctx.get_mut().unwrap();
+        let io = ctx.io_mut();
+
+        let display_size = image.size_f32() / image_target.scale_factor;
+        io.display_size = [display_size.x, display_size.y];
+        io.display_framebuffer_scale = [image_target.scale_factor, image_target.scale_factor];
+        io.mouse_pos = [-f32::MAX, -f32::MAX];
+
+        let ui_ptr = unsafe { NonNull::new_unchecked(ctx.new_frame()) };
+        target_context.ui = Some(ui_ptr);
+    }
 }
 
-fn imgui_end_frame_system(mut context: NonSendMut<ImguiContext>) {
+// Removes the pending settings handlers for the given target
+fn take_settings_handlers(
+    pending_settings_handlers: &mut Vec<(Option<Entity>, Box<dyn ImguiSettingsHandler>)>,
+    entity: Entity,
+    is_primary: bool,
+) -> Vec<Box<dyn ImguiSettingsHandler>> {
+    let (handlers, pending): (Vec<_>, Vec<_>) = std::mem::take(pending_settings_handlers)
+        .into_iter()
+        .partition(|(window, _)| window.map_or(is_primary, |window| window == entity));
+    *pending_settings_handlers = pending;
+    handlers.into_iter().map(|(_, handler)| handler).collect()
+}
+
+// Applies the style asset once it has loaded, and again whenever it is modified
+fn imgui_update_style_asset_system(
+    mut context: NonSendMut<ImguiContext>,
+    styles: Res<Assets<ImguiStyle>>,
+    mut events: EventReader<AssetEvent<ImguiStyle>>,
+) {
     let context = context.as_mut();
+    let Some(style_asset) = &context.style_asset else {
+        events.clear();
+        return;
+    };
+
+    if events.read().any(|e| e.is_modified(style_asset)) {
+        context.style_asset_changed = true;
+    }
+    if !context.style_asset_changed {
+        return;
+    }
+    if let Some(style) = styles.get(style_asset) {
+        context.style = style.clone();
+        context.style_asset_changed = false;
+        context.style_generation += 1;
+    }
+}
+
+// Resolves the data of the configured fonts once every font asset has loaded, so that the font
+// atlas of each target is rebuilt during the next Extract
+fn imgui_resolve_fonts_system(world: &mut World) {
+    let context = world.non_send_resource::<ImguiContext>();
+    if !context.font_atlas_config_changed {
+        return;
+    }
+    let fonts = ResolvedFonts::resolve(
+        &context.font_atlas_config,
+        &context.registered_glyphs,
+        world,
+    );
+
+    let mut context = world.non_send_resource_mut::<ImguiContext>();
+    let Some(fonts) = fonts else {
+        // Font assets are still loading, so the fallback fonts are replaced once they have loaded
+        context.font_rebuild_requested = true;
+        return;
+    };
+    context.fonts = Arc::new(fonts);
+    context.font_atlas_config_changed = false;
+    context.font_generation += 1;
+    if std::mem::take(&mut context.font_rebuild_requested) {
+        context.pending_rebuilt_font_generation = Some(context.font_generation);
+    }
+}
+
+// Sends `ImguiFontAtlasRebuilt` once the font atlas of every target has been rebuilt
+fn imgui_font_atlas_rebuilt_system(
+    mut context: NonSendMut<ImguiContext>,
+    mut events: EventWriter<ImguiFontAtlasRebuilt>,
+) {
+    let Some(font_generation) = context.pending_rebuilt_font_generation else {
+        return; // No rebuild was requested
+    };
+    if !context.targets.is_empty()
+        && context.targets.values().all(|target_context| {
+            target_context.font_generation.load(Ordering::Relaxed) >= font_generation
+        })
+    {
+        context.pending_rebuilt_font_generation = None;
+        events.write(ImguiFontAtlasRebuilt);
+    }
+}
+
+fn imgui_end_frame_system(
+    mut context: NonSendMut<ImguiContext>,
+    mut app_exit: EventReader<AppExit>,
+) {
+    // Settings are saved one final time when the app exits
+    let exiting = app_exit.read().count() > 0;
+
+    for target_context in context.targets.values_mut() {
+        if target_context.ui.is_none() {
+            continue; // No frame was started for this target
+        }
+        target_context.activate();
+        if exiting {
+            target_context.save_final_settings();
+        } else {
+            target_context.save_settings(false);
+        }
 
-    // End the imgui frame.
-    let draw_data = context.ctx.get_mut().unwrap().render();
+        // End the imgui frame.
+        let ctx = target_context.ctx.get_mut().unwrap();
+        let draw_data = ctx.render();
 
-    context.ui = None;
-    *context.rendered_draw_data.get_mut().unwrap() = OwnedDrawData::from(draw_data);
+        target_context.ui = None;
+        *target_context.rendered_draw_data.get_mut().unwrap() = OwnedDrawData::from(draw_data);
+
+        #[cfg(feature = "docking")]
+        if let Some(viewports) = &target_context.viewports {
+            viewports::update_platform_windows(ctx, viewports);
+        }
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn imgui_extract_frame_system(
-    primary_window: Extract<Query<&Window, With<PrimaryWindow>>>,
-    mut other_context: Extract<NonSend<ImguiContext>>,
+    windows: Extract<Query<&Window>>,
+    image_targets: Extract<Query<&ImguiImageTarget>>,
+    other_context: Extract<NonSend<ImguiContext>>,
     mut context: ResMut<ImguiRenderContext>,
-    extracted_windows: ResMut<ExtractedWindows>,
+    extracted_windows: Res<ExtractedWindows>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
     device: Res<RenderDevice>,
     queue: ResMut<RenderQueue>,
     _non_send: NonSend<NonSendHack>,
 ) {
-    // Get the rendered imgui frame data.
-    let owned_draw_data = {
-        let mut rendered = other_context.rendered_draw_data.write().unwrap();
-        std::mem::take(rendered.deref_mut())
-    };
+    let context = context.as_mut();
 
-    // Get the current display scale and ImGuiContext
-    let display_scale = {
-        if let Ok(single) = primary_window.single() {
-            single.scale_factor()
-        } else {
-            // Fall back to the previously captured display scale. This can happen during app shutdown.
-            context.display_scale
-        }
-    };
+    // The targets (and viewport windows) that are extracted this frame, so that the render state
+    // of targets whose ImGui context has been destroyed can be released
+    let mut extracted = HashSet::new();
 
-    // Determine the current texture format of the primary window
-    let Some(primary) = extracted_windows.primary else {
-        return;
-    };
-    let Some(extracted_window) = extracted_windows.windows.get(&primary) else {
-        return;
-    };
-    let Some(texture_format) = extracted_window.swap_chain_texture_format else {
-        return;
-    };
+    let mut texture_modify = other_context.texture_modify.write().unwrap();
 
-    // We've now recorded the draw data for the current frame, and this should be renderer agnostic.
-    // So at this point, we can check to see whether the texture format of the target window matches
-    // the renderer's texture format. If it doesn't, we recreate the Renderer here before we proceed
-    // to render the frame.
-    // We also recreate the renderer and the font atlas if the system display scale has changed, since
-    // this is the only safe point in the frame to do so.
-    context.draw = OwnedDrawDataWrap::default();
-    if texture_format != context.texture_format || context.display_scale != display_scale {
-        let renderer_config = RendererConfig {
-            texture_format,
-            ..default()
+    // Draw callbacks are shared by all targets, and are cheap to clone
+    context.draw_callbacks = other_context.draw_callbacks.clone();
+
+    let fonts = other_context.fonts.clone();
+
+    for (entity, target_context) in other_context.targets.iter() {
+        extracted.insert(*entity);
+
+        // Get the rendered imgui frame data.
+        let owned_draw_data = {
+            let mut rendered = target_context.rendered_draw_data.write().unwrap();
+            std::mem::take(rendered.deref_mut())
+        };
+
+        let image_target = image_targets.get(*entity).ok();
+
+        // Get the current display scale of the target. For windows, fall back to the previously
+        // captured display scale. This can happen during app shutdown.
+        let display_scale = match image_target {
+            Some(image_target) => Some(image_target.scale_factor),
+            None => windows
+                .get(*entity)
+                .map(|window| window.scale_factor())
+                .ok()
+                .or(context
+                    .targets
+                    .get(entity)
+                    .map(|target_render_context| target_render_context.display_scale)),
+        };
+
+        // Determine the current texture format of the target window or image
+        let texture_format = match image_target {
+            Some(image_target) => gpu_images
+                .get(&image_target.image)
+                .map(|gpu_image| gpu_image.texture_format),
+            None => extracted_windows
+                .windows
+                .get(entity)
+                .and_then(|extracted_window| extracted_window.swap_chain_texture_format),
+        };
+
+        // We've now recorded the draw data for the current frame, and this should be renderer agnostic.
+        // So at this point, we can check to see whether the texture format of the target matches
+        // the renderer's texture format. If it doesn't (or there is no renderer for this target yet), we
+        // recreate the Renderer here before we proceed to render the frame.
+        // We also recreate the renderer and the font atlas if the system display scale has changed, since
+        // this is the only safe point in the frame to do so.
+        let mut recreated = false;
+        if let (Some(display_scale), Some(texture_format)) = (display_scale, texture_format) {
+            let previous = context.targets.get(entity);
+            if previous.is_none_or(|previous| {
+                texture_format != previous.texture_format || display_scale != previous.display_scale
+            }) {
+                // The font atlas is rebuilt first, so that the renderer uploads the new atlas
+                update_display_scale(display_scale, &context.plugin, &fonts, target_context);
+                target_context
+                    .font_generation
+                    .store(other_context.font_generation, Ordering::Relaxed);
+                let renderer = create_renderer(
+                    &context.plugin,
+                    texture_format,
+                    &mut target_context.ctx.write().unwrap(),
+                    &device,
+                    &queue,
+                );
+
+                context.targets.insert(
+                    *entity,
+                    ImguiTargetRenderContext {
+                        target: ImguiRenderTarget::Window,
+                        renderer: RwLock::new(renderer),
+                        texture_format,
+                        draw: OwnedDrawDataWrap::default(),
+                        display_scale,
+                        // Re-add all textures
+                        textures_to_add: other_context.textures.clone(),
+                        textures_to_remove: Vec::new(),
+                    },
+                );
+                recreated = true;
+            }
+        }
+
+        let Some(target_render_context) = context.targets.get_mut(entity) else {
+            continue;
         };
-        context.renderer = RwLock::new(Renderer::new(
-            &mut other_context.ctx.write().unwrap(),
-            device.wgpu_device(),
-            &queue,
-            renderer_config,
-        ));
-        context.texture_format = texture_format;
-
-        update_display_scale(
-            context.display_scale,
-            display_scale,
-            &context.plugin,
-            other_context.deref_mut(),
-            &mut context.renderer.write().unwrap(),
-            &device,
-            &queue,
-        );
-        context.display_scale = display_scale;
 
-        // Re-add all textures
-        for texture_id in other_context.textures.keys() {
-            context
-                .textures_to_add
-                .insert(*texture_id, other_context.textures[texture_id].clone());
+        // Rebuild the font atlas if the fonts have changed since it was built
+        let font_atlas_rebuilt =
+            target_context.font_generation.load(Ordering::Relaxed) != other_context.font_generation;
+        if font_atlas_rebuilt {
+            target_context.activate();
+            let mut ctx = target_context.ctx.write().unwrap();
+            rebuild_font_atlas(
+                target_render_context.display_scale,
+                &context.plugin,
+                &fonts,
+                target_context,
+                ctx.deref_mut(),
+            );
+
+            // This replaces the font texture in the renderer, leaving any other textures intact
+            target_render_context
+                .renderer
+                .get_mut()
+                .unwrap()
+                .reload_font_texture(ctx.deref_mut(), device.wgpu_device(), &queue);
+            target_context
+                .font_generation
+                .store(other_context.font_generation, Ordering::Relaxed);
         }
-    } else {
-        // Just add the textures that have been registered this frame
-        for texture_id in other_context.texture_modify.read().unwrap().to_add.iter() {
-            context
-                .textures_to_add
-                .insert(*texture_id, other_context.textures[texture_id].clone());
+
+        if !recreated {
+            // Just add the textures that have been registered this frame
+            for texture_id in texture_modify.to_add.iter() {
+                target_render_context
+                    .textures_to_add
+                    .insert(*texture_id, other_context.textures[texture_id].clone());
+            }
         }
-    }
 
-    let mut texture_modify = other_context.texture_modify.write().unwrap();
+        target_render_context.target = match image_target {
+            Some(image_target) => ImguiRenderTarget::Image {
+                image: image_target.image.id(),
+                clear_color: image_target.clear_color,
+                has_view: false,
+            },
+            None => ImguiRenderTarget::Window,
+        };
+        target_render_context
+            .textures_to_remove
+            .extend_from_slice(&texture_modify.to_remove);
+        target_render_context.draw = OwnedDrawDataWrap(owned_draw_data);
+
+        #[cfg(feature = "docking")]
+        if let Some(viewports) = &target_context.viewports {
+            let display_scale = target_render_context.display_scale;
+            viewports::extract_viewports(
+                context,
+                target_context,
+                viewports,
+                &other_context.textures,
+                &texture_modify,
+                recreated || font_atlas_rebuilt,
+                display_scale,
+                &extracted_windows,
+                &device,
+                &queue,
+                &mut extracted,
+            );
+        }
+    }
 
     context
-        .textures_to_remove
-        .clone_from(&texture_modify.to_remove);
-    context.draw = OwnedDrawDataWrap(owned_draw_data);
+        .targets
+        .retain(|entity, _| extracted.contains(entity));
+
     texture_modify.to_add.clear();
     texture_modify.to_remove.clear();
 }
 
-fn imgui_update_textures_system(
+// Determines which view each window or image target's ImGui context should be drawn on top of
+fn imgui_prepare_views_system(
+    cameras: Query<(Entity, &ExtractedCamera)>,
     mut context: ResMut<ImguiRenderContext>,
-    device: Res<RenderDevice>,
-    gpu_images: Res<RenderAssets<GpuImage>>,
 ) {
-    // Remove all textures that are flagged for removal
     let context = context.as_mut();
-    let renderer = context.renderer.get_mut().unwrap();
-    let textures_to_remove = context.textures_to_remove.clone();
-    context.textures_to_remove.clear();
-    for texture_id in &textures_to_remove {
-        renderer.textures.remove(*texture_id);
-        context.textures_to_add.remove(texture_id);
+
+    let mut image_targets = HashMap::<AssetId<Image>, Entity>::new();
+    for (entity, target_render_context) in context.targets.iter_mut() {
+        if let ImguiRenderTarget::Image {
+            image, has_view, ..
+        } = &mut target_render_context.target
+        {
+            image_targets.insert(*image, *entity);
+            *has_view = false;
+        }
     }
 
-    // Add new textures
-    let mut added_textures = Vec::<TextureId>::new();
-    for (texture_id, handle) in &context.textures_to_add {
-        let mut renderer = context.renderer.write().unwrap();
-        add_image_to_renderer(texture_id, handle, &gpu_images, &mut renderer, &device);
-        added_textures.push(*texture_id);
+    // Find the last camera (by order) that renders to each target
+    let mut last_views = HashMap::<Entity, (isize, Entity)>::new();
+    for (view, camera) in &cameras {
+        let target = match &camera.target {
+            Some(NormalizedRenderTarget::Window(window)) => window.entity(),
+            Some(NormalizedRenderTarget::Image(image)) => {
+                let Some(target) = image_targets.get(&image.handle.id()) else {
+                    continue;
+                };
+                *target
+            }
+            _ => continue,
+        };
+        let last_view = last_views.entry(target).or_insert((camera.order, view));
+        if camera.order >= last_view.0 {
+            *last_view = (camera.order, view);
+        }
     }
-    for texture_id in &added_textures {
-        context.textures_to_add.remove(texture_id);
+
+    for target in last_views.keys() {
+        if let Some(ImguiRenderTarget::Image { has_view, .. }) = context
+            .targets
+            .get_mut(target)
+            .map(|target_render_context| &mut target_render_context.target)
+        {
+            *has_view = true;
+        }
+    }
+
+    context.views = last_views
+        .into_iter()
+        .map(|(target, (_, view))| (view, target))
+        .collect();
+}
+
+fn imgui_update_textures_system(
+    mut context: ResMut<ImguiRenderContext>,
+    device: Res<RenderDevice>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+) {
+    for target_render_context in context.targets.values_mut() {
+        // Remove all textures that are flagged for removal
+        let renderer = target_render_context.renderer.get_mut().unwrap();
+        for texture_id in target_render_context.textures_to_remove.drain(..) {
+            renderer.textures.remove(texture_id);
+            target_render_context.textures_to_add.remove(&texture_id);
+        }
+
+        // Add new textures
+        for (texture_id, handle) in target_render_context.textures_to_add.drain() {
+            add_image_to_renderer(&texture_id, &handle, &gpu_images, renderer, &device);
+        }
     }
 }
 
@@ -842,3 +2130,188 @@ pub mod prelude {
     pub use crate::*;
     pub use imgui::*;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        rc::Rc,
+        sync::{atomic::AtomicUsize, Mutex, MutexGuard},
+    };
+
+    // Dear ImGui has a single current context, so tests that create one must run one at a time
+    pub(crate) fn lock_imgui() -> MutexGuard<'static, ()> {
+        static LOCK: Mutex<()> = Mutex::new(());
+        LOCK.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    // Creates an ImGui context that can start frames without a renderer
+    pub(crate) fn create_imgui_context() -> imgui::Context {
+        let mut ctx = imgui::Context::create();
+        ctx.set_ini_filename(None);
+        ctx.io_mut().display_size = [800.0, 600.0];
+        ctx.fonts().build_rgba32_texture();
+        ctx
+    }
+
+    // Counts the number of times that settings are saved
+    #[derive(Default)]
+    struct CountingSettingsStorage(AtomicUsize);
+
+    impl ImguiSettingsStorage for CountingSettingsStorage {
+        fn load(&self, _ini_filename: &std::path::Path) -> Option<String> {
+            None
+        }
+
+        fn save(&self, _ini_filename: &std::path::Path, _settings: &str) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn settings_are_saved_once_per_change() {
+        let _lock = lock_imgui();
+        let storage = Arc::new(CountingSettingsStorage::default());
+        let plugin = ImguiPlugin {
+            settings_storage: storage.clone(),
+            ..default()
+        };
+        let mut target_context = ImguiTargetContext::new(Some("test.ini".into()), &plugin);
+
+        let frame = |target_context: &mut ImguiTargetContext, changed: bool| {
+            let ctx = target_context.ctx.get_mut().unwrap();
+            ctx.io_mut().display_size = [800.0, 600.0];
+            ctx.new_frame();
+            ctx.render();
+            // ImGui requests a save once the autosave interval has passed after a change
+            ctx.io_mut().want_save_ini_settings |= changed;
+            target_context.save_settings(false);
+            storage.0.load(Ordering::Relaxed)
+        };
+
+        assert_eq!(frame(&mut target_context, true), 1);
+        assert_eq!(frame(&mut target_context, false), 1);
+        assert_eq!(frame(&mut target_context, true), 2);
+    }
+
+    // Reports itself dirty until it is written
+    struct DirtySettingsHandler(Rc<Cell<bool>>);
+
+    impl ImguiSettingsHandler for DirtySettingsHandler {
+        fn type_name(&self) -> &str {
+            "Dirty"
+        }
+
+        fn read_line(&mut self, _entry: &str, _line: &str) {}
+
+        fn write_all(&mut self, _writer: &mut ImguiSettingsWriter) {
+            self.0.set(false);
+        }
+
+        fn is_dirty(&mut self) -> bool {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn dirty_settings_handlers_are_saved() {
+        let _lock = lock_imgui();
+        let storage = Arc::new(CountingSettingsStorage::default());
+        let plugin = ImguiPlugin {
+            settings_storage: storage.clone(),
+            settings_autosave_interval: Duration::from_millis(20),
+            ..default()
+        };
+        let mut target_context = ImguiTargetContext::new(Some("test.ini".into()), &plugin);
+        let dirty = Rc::new(Cell::new(false));
+        target_context.add_settings_handler(Box::new(DirtySettingsHandler(dirty.clone())));
+
+        let frame = |target_context: &mut ImguiTargetContext| {
+            let ctx = target_context.ctx.get_mut().unwrap();
+            ctx.io_mut().display_size = [800.0, 600.0];
+            ctx.io_mut().delta_time = 0.015;
+            ctx.new_frame();
+            ctx.render();
+            target_context.save_settings(false);
+            storage.0.load(Ordering::Relaxed)
+        };
+
+        assert_eq!(frame(&mut target_context), 0);
+        dirty.set(true);
+        assert_eq!(frame(&mut target_context), 0);
+        assert_eq!(frame(&mut target_context), 1);
+        assert!(!dirty.get());
+        assert_eq!(frame(&mut target_context), 1);
+    }
+
+    #[test]
+    fn final_settings_are_saved_once() {
+        let _lock = lock_imgui();
+        let storage = Arc::new(CountingSettingsStorage::default());
+        let plugin = ImguiPlugin {
+            settings_storage: storage.clone(),
+            ..default()
+        };
+
+        // Dropping a context saves its settings
+        drop(ImguiTargetContext::new(Some("test.ini".into()), &plugin));
+        assert_eq!(storage.0.load(Ordering::Relaxed), 1);
+
+        // Unless they were already saved for the app exiting
+        let mut target_context = ImguiTargetContext::new(Some("test.ini".into()), &plugin);
+        target_context.save_final_settings();
+        assert_eq!(storage.0.load(Ordering::Relaxed), 2);
+        drop(target_context);
+        assert_eq!(storage.0.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn draw_callbacks_reset_render_state() {
+        let _lock = lock_imgui();
+        let mut ctx = create_imgui_context();
+
+        let ui = ctx.new_frame();
+        // Windows with a size are drawn on their first frame, rather than being measured
+        ui.window("Draw Callback")
+            .size([200.0, 100.0], imgui::Condition::Always)
+            .build(|| ImguiDrawCallbackId(7).add_to_window_draw_list(ui));
+        let draw_data = ctx.render();
+
+        let commands: Vec<_> = draw_data
+            .draw_lists()
+            .flat_map(|draw_list| draw_list.commands())
+            .collect();
+        let callback_index = commands
+            .iter()
+            .position(|command| match command {
+                imgui::DrawCmd::RawCallback { callback, raw_cmd } => {
+                    std::ptr::eq(
+                        *callback as *const (),
+                        imgui_draw_callback_marker as *const (),
+                    ) && unsafe { (**raw_cmd).UserCallbackData } as usize == 7
+                }
+                _ => false,
+            })
+            .expect("the draw callback should be in the draw list");
+        assert!(matches!(
+            commands.get(callback_index + 1),
+            Some(imgui::DrawCmd::ResetRenderState)
+        ));
+    }
+
+    #[test]
+    fn settings_save_requests_reach_their_own_context() {
+        let _lock = lock_imgui();
+        let plugin = ImguiPlugin::default();
+        let mut first = ImguiTargetContext::new(None, &plugin);
+        let mut second = ImguiTargetContext::new(None, &plugin);
+
+        // The second context is active after it is created
+        first.request_save();
+        for (target_context, want_save) in [(&mut first, true), (&mut second, false)] {
+            target_context.activate();
+            let io = target_context.ctx.get_mut().unwrap().io();
+            assert_eq!(io.want_save_ini_settings, want_save);
+        }
+    }
+}
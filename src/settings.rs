@@ -0,0 +1,244 @@
+// Persistence of ImGui's ini settings (window positions, sizes, etc)
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Persists the ini settings of each ImGui context (window positions, sizes, etc).
+///
+/// Settings are identified by the `ini_filename` of each context (see `ImguiPlugin`,
+/// `ImguiWindow` and `ImguiImageTarget`). They are loaded when the context is created, and saved
+/// periodically while they change, when the context is destroyed, and when the app exits.
+pub trait ImguiSettingsStorage: Send + Sync + 'static {
+    /// Returns the settings previously saved under the given ini filename, if any.
+    fn load(&self, ini_filename: &Path) -> Option<String>;
+
+    /// Saves the settings under the given ini filename.
+    fn save(&self, ini_filename: &Path, settings: &str);
+}
+
+/// Stores ini settings in files, at the path given by each ini filename. This is the default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ImguiFileSettingsStorage;
+
+impl ImguiSettingsStorage for ImguiFileSettingsStorage {
+    fn load(&self, ini_filename: &Path) -> Option<String> {
+        std::fs::read_to_string(ini_filename).ok()
+    }
+
+    fn save(&self, ini_filename: &Path, settings: &str) {
+        if let Err(error) = std::fs::write(ini_filename, settings) {
+            log::warn!(
+                "Could not save ImGui settings to {}: {error}",
+                ini_filename.display()
+            );
+        }
+    }
+}
+
+/// Stores ini settings in memory, so that they persist for the lifetime of the app only.
+/// This is useful on platforms without a file system, such as the web.
+#[derive(Debug, Default)]
+pub struct ImguiMemorySettingsStorage(Mutex<HashMap<PathBuf, String>>);
+
+impl ImguiSettingsStorage for ImguiMemorySettingsStorage {
+    fn load(&self, ini_filename: &Path) -> Option<String> {
+        self.0.lock().unwrap().get(ini_filename).cloned()
+    }
+
+    fn save(&self, ini_filename: &Path, settings: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(ini_filename.to_owned(), settings.to_owned());
+    }
+}
+
+/// Persists game-specific state (e.g. the last opened tab of a tool) in the ini settings of an
+/// ImGui context, alongside ImGui's own settings. Register handlers with
+/// `ImguiContext::add_settings_handler`.
+///
+/// As with Dear ImGui's own settings handlers, state is stored in entries of the form:
+///
+/// ```ini
+/// [TypeName][EntryName]
+/// key=value
+/// ```
+///
+/// Handlers are owned by the ImGui context, so any state that is also used elsewhere should be
+/// shared with the handler (e.g. via `Arc<Mutex<T>>`). ImGui only saves its settings when its
+/// own state changes, so a handler whose state changes elsewhere should report it from
+/// `is_dirty` to have the settings saved.
+pub trait ImguiSettingsHandler: 'static {
+    /// The type name that identifies this handler's entries.
+    fn type_name(&self) -> &str;
+
+    /// Called with each line of each of this handler's entries when the settings are loaded.
+    fn read_line(&mut self, entry: &str, line: &str);
+
+    /// Called when the settings are saved, to write all of this handler's entries.
+    fn write_all(&mut self, writer: &mut ImguiSettingsWriter);
+
+    /// Returns whether this handler's state has changed since its entries were last written, in
+    /// which case the settings are saved after `ImguiPlugin::settings_autosave_interval`. This is
+    /// checked every frame while no save is pending, so it should remain true until
+    /// `write_all` is called. The default returns false, so that the handler's entries are only
+    /// written when ImGui's own settings are saved.
+    fn is_dirty(&mut self) -> bool {
+        false
+    }
+}
+
+/// Writes the entries of an `ImguiSettingsHandler` to the ini settings.
+pub struct ImguiSettingsWriter<'a> {
+    type_name: &'a str,
+    settings: &'a mut String,
+    has_entry: bool,
+}
+
+impl ImguiSettingsWriter<'_> {
+    /// Starts a new entry with the given name. Subsequent lines are written to this entry.
+    pub fn entry(&mut self, name: &str) {
+        // Entries are separated by a blank line, as in Dear ImGui's own settings
+        if self.has_entry {
+            self.settings.push('\n');
+        }
+        self.has_entry = true;
+        self.settings
+            .push_str(&format!("[{}][{name}]\n", self.type_name));
+    }
+
+    /// Writes a line to the current entry.
+    pub fn line(&mut self, line: &str) {
+        self.settings.push_str(line);
+        self.settings.push('\n');
+    }
+}
+
+// Writes the entries of the given handler to the end of the ini settings
+pub(crate) fn write_settings(settings: &mut String, handler: &mut dyn ImguiSettingsHandler) {
+    let type_name = handler.type_name().to_owned();
+    let mut writer = ImguiSettingsWriter {
+        type_name: &type_name,
+        settings,
+        has_entry: false,
+    };
+    handler.write_all(&mut writer);
+    if writer.has_entry {
+        writer.settings.push('\n');
+    }
+}
+
+// Reads the lines of each of the given handler's entries from the ini settings
+pub(crate) fn read_settings(settings: &str, handler: &mut dyn ImguiSettingsHandler) {
+    let type_name = handler.type_name().to_owned();
+    let mut entry = None;
+    for line in settings.lines().map(str::trim_end) {
+        if let Some(header) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            // Entry headers are of the form [TypeName][EntryName]
+            entry = header
+                .split_once("][")
+                .filter(|(entry_type_name, _)| *entry_type_name == type_name)
+                .map(|(_, entry_name)| entry_name);
+        } else if let Some(entry) = entry.filter(|_| !line.is_empty()) {
+            handler.read_line(entry, line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stores the lines of each entry in the order that they were read
+    struct TestHandler {
+        type_name: &'static str,
+        entries: Vec<(String, Vec<String>)>,
+    }
+
+    impl TestHandler {
+        fn new(type_name: &'static str, entries: &[(&str, &[&str])]) -> Self {
+            Self {
+                type_name,
+                entries: entries
+                    .iter()
+                    .map(|(name, lines)| {
+                        let lines = lines.iter().map(|line| line.to_string()).collect();
+                        (name.to_string(), lines)
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl ImguiSettingsHandler for TestHandler {
+        fn type_name(&self) -> &str {
+            self.type_name
+        }
+
+        fn read_line(&mut self, entry: &str, line: &str) {
+            match self.entries.last_mut() {
+                Some((name, lines)) if name == entry => lines.push(line.to_owned()),
+                _ => self.entries.push((entry.to_owned(), vec![line.to_owned()])),
+            }
+        }
+
+        fn write_all(&mut self, writer: &mut ImguiSettingsWriter) {
+            for (name, lines) in &self.entries {
+                writer.entry(name);
+                for line in lines {
+                    writer.line(line);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn settings_round_trip() {
+        let mut handler = TestHandler::new(
+            "Tool",
+            &[("Window", &["Open=1", "Tab=2"]), ("Other", &["Value=x"])],
+        );
+        let mut settings = String::from("[Window][Debug##Default]\nPos=60,60\n\n");
+        write_settings(&mut settings, &mut handler);
+        assert_eq!(
+            settings,
+            "[Window][Debug##Default]\nPos=60,60\n\n\
+             [Tool][Window]\nOpen=1\nTab=2\n\n\
+             [Tool][Other]\nValue=x\n\n"
+        );
+
+        let mut read_handler = TestHandler::new("Tool", &[]);
+        read_settings(&settings, &mut read_handler);
+        assert_eq!(read_handler.entries, handler.entries);
+    }
+
+    #[test]
+    fn settings_are_read_by_their_own_handler() {
+        let mut first = TestHandler::new("First", &[("Entry", &["A=1"])]);
+        let mut second = TestHandler::new("Second", &[("Entry", &["B=2"])]);
+        let mut settings = String::new();
+        write_settings(&mut settings, &mut first);
+        write_settings(&mut settings, &mut second);
+
+        let mut read_first = TestHandler::new("First", &[]);
+        let mut read_second = TestHandler::new("Second", &[]);
+        read_settings(&settings, &mut read_first);
+        read_settings(&settings, &mut read_second);
+        assert_eq!(read_first.entries, first.entries);
+        assert_eq!(read_second.entries, second.entries);
+    }
+
+    #[test]
+    fn settings_without_entries_are_empty() {
+        let mut handler = TestHandler::new("Tool", &[]);
+        let mut settings = String::new();
+        write_settings(&mut settings, &mut handler);
+        assert!(settings.is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+// Styles for ImGui, which can be loaded from Bevy assets
+
+use bevy::prelude::*;
+use imgui::StyleColor;
+use std::collections::HashMap;
+
+#[cfg(feature = "style_asset")]
+use bevy::asset::{io::Reader, AssetLoader, LoadContext};
+#[cfg(feature = "style_asset")]
+use serde::{Deserialize, Serialize};
+
+/// A style for ImGui, consisting of a preset along with any colours and sizes that override it.
+///
+/// Sizes are specified in unscaled pixels, and are scaled by the display scale of each window
+/// or image target when the style is applied. A style can be applied with
+/// `ImguiContext::set_style`, or loaded from an asset and applied with
+/// `ImguiContext::set_style_asset`.
+///
+/// With the `style_asset` cargo feature, styles can be loaded from `.imgui.ron` and
+/// `.imgui.json` files, and are reapplied whenever the asset is modified. For example:
+///
+/// ```ron
+/// (
+///     preset: Light,
+///     window_rounding: Some(4.0),
+///     frame_padding: Some((6.0, 4.0)),
+///     colors: {
+///         "WindowBg": (0.95, 0.95, 0.95, 1.0),
+///     },
+/// )
+/// ```
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+#[cfg_attr(feature = "style_asset", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "style_asset", serde(default))]
+pub struct ImguiStyle {
+    /// The preset that the style is based on (default is `ImguiStylePreset::Dark`).
+    pub preset: ImguiStylePreset,
+
+    /// Overrides the global alpha applied to everything.
+    pub alpha: Option<f32>,
+
+    /// Overrides the padding within windows.
+    pub window_padding: Option<[f32; 2]>,
+
+    /// Overrides the corner rounding of windows.
+    pub window_rounding: Option<f32>,
+
+    /// Overrides the border size of windows.
+    pub window_border_size: Option<f32>,
+
+    /// Overrides the corner rounding of child windows.
+    pub child_rounding: Option<f32>,
+
+    /// Overrides the border size of child windows.
+    pub child_border_size: Option<f32>,
+
+    /// Overrides the corner rounding of popups.
+    pub popup_rounding: Option<f32>,
+
+    /// Overrides the border size of popups.
+    pub popup_border_size: Option<f32>,
+
+    /// Overrides the padding within framed widgets.
+    pub frame_padding: Option<[f32; 2]>,
+
+    /// Overrides the corner rounding of framed widgets.
+    pub frame_rounding: Option<f32>,
+
+    /// Overrides the border size of framed widgets.
+    pub frame_border_size: Option<f32>,
+
+    /// Overrides the spacing between widgets.
+    pub item_spacing: Option<[f32; 2]>,
+
+    /// Overrides the spacing between the elements of a composite widget.
+    pub item_inner_spacing: Option<[f32; 2]>,
+
+    /// Overrides the horizontal indentation of tree nodes.
+    pub indent_spacing: Option<f32>,
+
+    /// Overrides the width of vertical scrollbars and height of horizontal scrollbars.
+    pub scrollbar_size: Option<f32>,
+
+    /// Overrides the corner rounding of scrollbar grabs.
+    pub scrollbar_rounding: Option<f32>,
+
+    /// Overrides the minimum size of slider and scrollbar grabs.
+    pub grab_min_size: Option<f32>,
+
+    /// Overrides the corner rounding of slider grabs.
+    pub grab_rounding: Option<f32>,
+
+    /// Overrides the corner rounding of tabs.
+    pub tab_rounding: Option<f32>,
+
+    /// Overrides colours, keyed by the name of the `imgui::StyleColor` (e.g. "WindowBg"),
+    /// as RGBA in the range 0 to 1.
+    pub colors: HashMap<String, [f32; 4]>,
+}
+
+impl ImguiStyle {
+    /// Creates a style from the given preset, without any overrides.
+    pub fn preset(preset: ImguiStylePreset) -> Self {
+        Self {
+            preset,
+            ..default()
+        }
+    }
+
+    // Applies this style to an unscaled ImGui style
+    pub(crate) fn apply(&self, style: &mut imgui::Style) {
+        match self.preset {
+            ImguiStylePreset::Dark => {
+                style.use_dark_colors();
+            }
+            ImguiStylePreset::Light => {
+                style.use_light_colors();
+            }
+            ImguiStylePreset::Classic => {
+                style.use_classic_colors();
+            }
+            ImguiStylePreset::HighContrast => use_high_contrast_style(style),
+        }
+
+        let overrides = [
+            (&mut style.window_rounding, self.window_rounding),
+            (&mut style.window_border_size, self.window_border_size),
+            (&mut style.child_rounding, self.child_rounding),
+            (&mut style.child_border_size, self.child_border_size),
+            (&mut style.popup_rounding, self.popup_rounding),
+            (&mut style.popup_border_size, self.popup_border_size),
+            (&mut style.frame_rounding, self.frame_rounding),
+            (&mut style.frame_border_size, self.frame_border_size),
+            (&mut style.indent_spacing, self.indent_spacing),
+            (&mut style.scrollbar_size, self.scrollbar_size),
+            (&mut style.scrollbar_rounding, self.scrollbar_rounding),
+            (&mut style.grab_min_size, self.grab_min_size),
+            (&mut style.grab_rounding, self.grab_rounding),
+            (&mut style.tab_rounding, self.tab_rounding),
+            (&mut style.alpha, self.alpha),
+        ];
+        for (value, style_override) in overrides {
+            if let Some(style_override) = style_override {
+                *value = style_override;
+            }
+        }
+
+        let overrides = [
+            (&mut style.window_padding, self.window_padding),
+            (&mut style.frame_padding, self.frame_padding),
+            (&mut style.item_spacing, self.item_spacing),
+            (&mut style.item_inner_spacing, self.item_inner_spacing),
+        ];
+        for (value, style_override) in overrides {
+            if let Some(style_override) = style_override {
+                *value = style_override;
+            }
+        }
+
+        for (name, color) in self.colors.iter() {
+            match StyleColor::VARIANTS
+                .iter()
+                .find(|style_color| format!("{style_color:?}") == *name)
+            {
+                Some(style_color) => style[*style_color] = *color,
+                None => log::warn!("Ignoring unknown ImGui style colour: {name}"),
+            }
+        }
+    }
+}
+
+/// The built-in styles that an `ImguiStyle` can be based on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "style_asset", derive(Serialize, Deserialize))]
+pub enum ImguiStylePreset {
+    /// Dear ImGui's default dark style.
+    #[default]
+    Dark,
+
+    /// Dear ImGui's light style.
+    Light,
+
+    /// Dear ImGui's classic style.
+    Classic,
+
+    /// A high contrast style, with white text and borders on black backgrounds, and yellow
+    /// highlights, for accessibility.
+    HighContrast,
+}
+
+fn use_high_contrast_style(style: &mut imgui::Style) {
+    style.use_dark_colors();
+
+    let black = [0.0, 0.0, 0.0, 1.0];
+    let white = [1.0, 1.0, 1.0, 1.0];
+    let yellow = [1.0, 1.0, 0.0, 1.0];
+    let blue = [0.0, 0.25, 0.75, 1.0];
+    let light_blue = [0.0, 0.45, 1.0, 1.0];
+
+    for (style_color, color) in [
+        (StyleColor::Text, white),
+        (StyleColor::TextDisabled, [0.7, 0.7, 0.7, 1.0]),
+        (StyleColor::WindowBg, black),
+        (StyleColor::ChildBg, black),
+        (StyleColor::PopupBg, black),
+        (StyleColor::Border, white),
+        (StyleColor::FrameBg, black),
+        (StyleColor::FrameBgHovered, blue),
+        (StyleColor::FrameBgActive, light_blue),
+        (StyleColor::TitleBg, black),
+        (StyleColor::TitleBgActive, blue),
+        (StyleColor::TitleBgCollapsed, black),
+        (StyleColor::MenuBarBg, black),
+        (StyleColor::ScrollbarBg, black),
+        (StyleColor::ScrollbarGrab, white),
+        (StyleColor::ScrollbarGrabHovered, yellow),
+        (StyleColor::ScrollbarGrabActive, yellow),
+        (StyleColor::CheckMark, yellow),
+        (StyleColor::SliderGrab, white),
+        (StyleColor::SliderGrabActive, yellow),
+        (StyleColor::Button, blue),
+        (StyleColor::ButtonHovered, light_blue),
+        (StyleColor::ButtonActive, yellow),
+        (StyleColor::Header, blue),
+        (StyleColor::HeaderHovered, light_blue),
+        (StyleColor::HeaderActive, light_blue),
+        (StyleColor::Separator, white),
+        (StyleColor::SeparatorHovered, yellow),
+        (StyleColor::SeparatorActive, yellow),
+        (StyleColor::ResizeGrip, white),
+        (StyleColor::ResizeGripHovered, yellow),
+        (StyleColor::ResizeGripActive, yellow),
+        (StyleColor::Tab, black),
+        (StyleColor::TabHovered, light_blue),
+        (StyleColor::TabActive, blue),
+        (StyleColor::TextSelectedBg, [0.0, 0.45, 1.0, 0.6]),
+        (StyleColor::NavHighlight, yellow),
+    ] {
+        style[style_color] = color;
+    }
+
+    style.window_border_size = 1.0;
+    style.child_border_size = 1.0;
+    style.popup_border_size = 1.0;
+    style.frame_border_size = 1.0;
+}
+
+/// Loads `ImguiStyle` assets from `.imgui.ron` and `.imgui.json` files.
+#[cfg(feature = "style_asset")]
+#[derive(Default)]
+pub(crate) struct ImguiStyleLoader;
+
+#[cfg(feature = "style_asset")]
+impl AssetLoader for ImguiStyleLoader {
+    type Asset = ImguiStyle;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<ImguiStyle, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let is_json = load_context
+            .path()
+            .extension()
+            .is_some_and(|extension| extension == "json");
+        if is_json {
+            Ok(serde_json::from_slice(&bytes)?)
+        } else {
+            Ok(ron::de::from_bytes(&bytes)?)
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["imgui.ron", "imgui.json"]
+    }
+}
@@ -0,0 +1,150 @@
+// Access to the ImGui UI from Bevy systems, as an alternative to `NonSendMut<ImguiContext>`
+
+use bevy::{
+    ecs::system::{SystemName, SystemParam},
+    prelude::*,
+};
+use std::{
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::Mutex,
+};
+
+use crate::ImguiContext;
+
+/// A system parameter that provides the `imgui::Ui` of the primary window, which it derefs to.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_mod_imgui::prelude::*;
+/// fn my_ui(ui: ImguiUi) {
+///     ui.window("Hello").build(|| ui.text("Hello world!"));
+/// }
+/// ```
+///
+/// ImGui is not thread safe, so systems with this parameter run on the main thread, one at a
+/// time. Systems that would rather run in parallel can queue UI with `ImguiUiQueue` instead.
+///
+/// Dereferencing panics if the primary window has no ImGui context, or if an imgui frame is not
+/// currently being rendered (see `ImguiSet`), naming the system that used it. Use `try_ui` where
+/// this may be the case.
+#[derive(SystemParam)]
+pub struct ImguiUi<'w, 's> {
+    context: NonSendMut<'w, ImguiContext>,
+    system: SystemName<'s>,
+    // Whether this system has been warned about skipping its UI
+    warned: Local<'s, bool>,
+}
+
+impl ImguiUi<'_, '_> {
+    /// Provides the `imgui::Ui` of the primary window, or None if the primary window has no
+    /// ImGui context, or if an imgui frame is not currently being rendered. See
+    /// `ImguiContext::try_ui`.
+    pub fn try_ui(&mut self) -> Option<&mut imgui::Ui> {
+        let ui = self.context.try_ui();
+        // Only the first skipped frame of each system is reported, as systems use this where
+        // they expect to run outside of an imgui frame
+        if ui.is_none() && !std::mem::replace(&mut *self.warned, true) {
+            log::warn!(
+                "{} used ImguiUi outside of an imgui frame, so its UI was skipped",
+                self.system.name()
+            );
+        }
+        ui
+    }
+
+    /// Provides the `imgui::Ui` of the given window, or of the given entity's `ImguiImageTarget`.
+    /// See `ImguiContext::window_ui`.
+    pub fn window_ui(&mut self, window: Entity) -> Option<&mut imgui::Ui> {
+        self.context.window_ui(window)
+    }
+
+    /// Provides the ImGui context resource, e.g. for registering textures.
+    pub fn context(&mut self) -> &mut ImguiContext {
+        &mut self.context
+    }
+
+    // Activates the context of the primary window and returns its Ui, panicking if there is none
+    fn primary_ui(&self) -> NonNull<imgui::Ui> {
+        let ui = self
+            .context
+            .primary_window
+            .and_then(|primary_window| self.context.targets.get(&primary_window))
+            .and_then(|target_context| {
+                target_context.activate();
+                target_context.ui
+            });
+        ui.unwrap_or_else(|| {
+            panic!(
+                "{} used ImguiUi outside of an imgui frame! Systems that use ImguiUi must run \
+                after ImguiSet::NewFrame and before ImguiSet::EndFrame (e.g. in Update or \
+                PostUpdate), or use ImguiUi::try_ui.",
+                self.system.name()
+            )
+        })
+    }
+}
+
+impl Deref for ImguiUi<'_, '_> {
+    type Target = imgui::Ui;
+
+    fn deref(&self) -> &imgui::Ui {
+        unsafe { self.primary_ui().as_ref() }
+    }
+}
+
+impl DerefMut for ImguiUi<'_, '_> {
+    fn deref_mut(&mut self) -> &mut imgui::Ui {
+        unsafe { self.primary_ui().as_mut() }
+    }
+}
+
+type QueuedUi = Box<dyn FnOnce(&imgui::Ui) + Send>;
+
+/// A queue of UI to submit to ImGui, for systems that should not be confined to the main thread.
+///
+/// Systems take this resource with `Res<ImguiUiQueue>`, so they can run in parallel with each
+/// other. Queued UI is submitted at the end of the frame, on the main thread, in the order it was
+/// queued (which is unspecified between systems that run in parallel).
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_mod_imgui::prelude::*;
+/// fn my_ui(queue: Res<ImguiUiQueue>, time: Res<Time>) {
+///     let elapsed = time.elapsed_secs();
+///     queue.add(move |ui| {
+///         ui.window("Time").build(|| ui.text(format!("{elapsed:.1}s")));
+///     });
+/// }
+/// ```
+#[derive(Resource, Default)]
+pub struct ImguiUiQueue(Mutex<Vec<(Option<Entity>, QueuedUi)>>);
+
+impl ImguiUiQueue {
+    /// Queues UI to submit to the primary window.
+    pub fn add(&self, ui: impl FnOnce(&imgui::Ui) + Send + 'static) {
+        self.0.lock().unwrap().push((None, Box::new(ui)));
+    }
+
+    /// Queues UI to submit to the given window, or to the given entity's `ImguiImageTarget`.
+    /// The UI is discarded if the entity does not have an ImGui context.
+    pub fn add_window(&self, window: Entity, ui: impl FnOnce(&imgui::Ui) + Send + 'static) {
+        self.0.lock().unwrap().push((Some(window), Box::new(ui)));
+    }
+}
+
+// Submits the UI queued with `ImguiUiQueue` before the imgui frame ends
+pub(crate) fn imgui_submit_queued_ui_system(
+    mut context: NonSendMut<ImguiContext>,
+    queue: Res<ImguiUiQueue>,
+) {
+    let queued = std::mem::take(&mut *queue.0.lock().unwrap());
+    for (window, ui) in queued {
+        let Some(window) = window.or(context.primary_window) else {
+            continue;
+        };
+        if let Some(window_ui) = context.window_ui(window) {
+            ui(window_ui);
+        }
+    }
+}
@@ -0,0 +1,656 @@
+// Multi-viewport support, where ImGui windows that are dragged outside of the window that owns
+// their context become Bevy windows of their own. Enabled with the `docking` cargo feature.
+
+use bevy::{
+    asset::StrongHandle,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        renderer::{RenderDevice, RenderQueue},
+        view::ExtractedWindows,
+    },
+    window::{Monitor, WindowLevel, WindowRef, WindowResolution},
+    winit::WinitWindows,
+};
+use imgui::{
+    BackendFlags, ConfigFlags, OwnedDrawData, PlatformMonitor, PlatformViewportBackend, TextureId,
+    Viewport, ViewportFlags,
+};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ffi::c_void,
+    rc::Rc,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    create_renderer, input::ViewportInput, ImguiContext, ImguiRenderContext, ImguiRenderTarget,
+    ImguiTargetContext, ImguiTargetRenderContext, ImguiTextureModifyState, OwnedDrawDataWrap,
+};
+
+/// Marks a window that was spawned for an ImGui viewport, which is created when an ImGui window
+/// is dragged outside of the window that owns its ImGui context. Enable this with
+/// `ImguiPlugin::viewports`.
+///
+/// Viewport windows are spawned and despawned automatically, along with a `Camera2d` that
+/// renders to them, and their input is routed to the ImGui context of the owning window.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ImguiViewportWindow {
+    /// The window that owns the ImGui context of the viewport.
+    pub owner: Entity,
+}
+
+// The viewports of a single ImGui context, shared between its platform backend (called by ImGui)
+// and the systems that keep each viewport in sync with its Bevy window. Positions and sizes are
+// in logical pixels, relative to the desktop.
+#[derive(Default)]
+pub(crate) struct ViewportState {
+    // Viewports are keyed by an id stored in their platform user data, which is null for the
+    // main viewport (i.e. the owning window)
+    viewports: HashMap<usize, ViewportWindow>,
+    next_id: usize,
+    // The windows of destroyed viewports, which are despawned by the next update
+    destroyed: Vec<Entity>,
+    owner_position: Vec2,
+    owner_size: Vec2,
+    owner_focused: bool,
+}
+
+#[derive(Default)]
+struct ViewportWindow {
+    entity: Option<Entity>,
+    position: Vec2,
+    size: Vec2,
+    title: String,
+    decorations: bool,
+    taskbar_icon: bool,
+    top_most: bool,
+    focused: bool,
+    // Changes made by ImGui that have not been applied to the window yet
+    position_changed: bool,
+    size_changed: bool,
+    title_changed: bool,
+    focus_requested: bool,
+    // Changes made by the platform that have not been reported to ImGui yet
+    platform_moved: bool,
+    platform_resized: bool,
+    platform_closed: bool,
+    // The draw data rendered for this viewport by the last ImGui frame
+    draw_data: OwnedDrawData,
+}
+
+fn viewport_id(viewport: &Viewport) -> usize {
+    viewport.platform_user_data as usize
+}
+
+impl ViewportState {
+    // Returns the desktop position of the client area of the given window, if it is the window
+    // of one of these viewports
+    fn viewport_position(&self, entity: Entity) -> Option<Vec2> {
+        self.viewports
+            .values()
+            .find(|viewport| viewport.entity == Some(entity))
+            .map(|viewport| viewport.position)
+    }
+
+    // Returns the desktop position of the client area of the owning window
+    pub(crate) fn owner_position(&self) -> Vec2 {
+        self.owner_position
+    }
+
+    // Returns the windows that have been spawned for these viewports
+    pub(crate) fn windows(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.viewports
+            .values()
+            .filter_map(|viewport| viewport.entity)
+    }
+}
+
+// The platform backend that ImGui calls to manage the windows of its viewports. Windows are
+// spawned, despawned and updated by `imgui_update_viewports_system`, which also reports changes
+// made by the platform (e.g. windows being moved by the OS) back to ImGui.
+struct ImguiViewportBackend(Rc<RefCell<ViewportState>>);
+
+impl PlatformViewportBackend for ImguiViewportBackend {
+    fn create_window(&mut self, viewport: &mut Viewport) {
+        let mut state = self.0.borrow_mut();
+        state.next_id += 1;
+        let id = state.next_id;
+        viewport.platform_user_data = id as *mut c_void;
+        state.viewports.insert(
+            id,
+            ViewportWindow {
+                position: viewport.pos.into(),
+                size: viewport.size.into(),
+                decorations: !viewport.flags.contains(ViewportFlags::NO_DECORATION),
+                taskbar_icon: !viewport.flags.contains(ViewportFlags::NO_TASK_BAR_ICON),
+                top_most: viewport.flags.contains(ViewportFlags::TOP_MOST),
+                focused: !viewport
+                    .flags
+                    .contains(ViewportFlags::NO_FOCUS_ON_APPEARING),
+                ..default()
+            },
+        );
+    }
+
+    fn destroy_window(&mut self, viewport: &mut Viewport) {
+        let mut state = self.0.borrow_mut();
+        if let Some(window) = state.viewports.remove(&viewport_id(viewport)) {
+            state.destroyed.extend(window.entity);
+        }
+        viewport.platform_user_data = std::ptr::null_mut();
+    }
+
+    // Windows are shown as soon as they are spawned
+    fn show_window(&mut self, _viewport: &mut Viewport) {}
+
+    fn set_window_pos(&mut self, viewport: &mut Viewport, pos: [f32; 2]) {
+        if let Some(window) = self
+            .0
+            .borrow_mut()
+            .viewports
+            .get_mut(&viewport_id(viewport))
+        {
+            window.position = pos.into();
+            window.position_changed = true;
+        }
+    }
+
+    fn get_window_pos(&mut self, viewport: &mut Viewport) -> [f32; 2] {
+        let state = self.0.borrow();
+        match state.viewports.get(&viewport_id(viewport)) {
+            Some(window) => window.position.into(),
+            None => state.owner_position.into(),
+        }
+    }
+
+    fn set_window_size(&mut self, viewport: &mut Viewport, size: [f32; 2]) {
+        if let Some(window) = self
+            .0
+            .borrow_mut()
+            .viewports
+            .get_mut(&viewport_id(viewport))
+        {
+            window.size = size.into();
+            window.size_changed = true;
+        }
+    }
+
+    fn get_window_size(&mut self, viewport: &mut Viewport) -> [f32; 2] {
+        let state = self.0.borrow();
+        match state.viewports.get(&viewport_id(viewport)) {
+            Some(window) => window.size.into(),
+            None => state.owner_size.into(),
+        }
+    }
+
+    fn set_window_focus(&mut self, viewport: &mut Viewport) {
+        if let Some(window) = self
+            .0
+            .borrow_mut()
+            .viewports
+            .get_mut(&viewport_id(viewport))
+        {
+            window.focus_requested = true;
+        }
+    }
+
+    fn get_window_focus(&mut self, viewport: &mut Viewport) -> bool {
+        let state = self.0.borrow();
+        match state.viewports.get(&viewport_id(viewport)) {
+            Some(window) => window.focused,
+            None => state.owner_focused,
+        }
+    }
+
+    // Bevy does not report whether a window is minimized
+    fn get_window_minimized(&mut self, _viewport: &mut Viewport) -> bool {
+        false
+    }
+
+    fn set_window_title(&mut self, viewport: &mut Viewport, title: &str) {
+        if let Some(window) = self
+            .0
+            .borrow_mut()
+            .viewports
+            .get_mut(&viewport_id(viewport))
+        {
+            window.title = title.to_owned();
+            window.title_changed = true;
+        }
+    }
+
+    fn set_window_alpha(&mut self, _viewport: &mut Viewport, _alpha: f32) {}
+
+    // Called for each viewport at the end of each frame, to report changes made by the platform
+    fn update_window(&mut self, viewport: &mut Viewport) {
+        if let Some(window) = self
+            .0
+            .borrow_mut()
+            .viewports
+            .get_mut(&viewport_id(viewport))
+        {
+            viewport.platform_request_move |= std::mem::take(&mut window.platform_moved);
+            viewport.platform_request_resize |= std::mem::take(&mut window.platform_resized);
+            viewport.platform_request_close |= std::mem::take(&mut window.platform_closed);
+        }
+    }
+
+    // Viewports are rendered by `ImGuiNode`, on top of the camera of each viewport window
+    fn render_window(&mut self, _viewport: &mut Viewport) {}
+
+    fn swap_buffers(&mut self, _viewport: &mut Viewport) {}
+
+    fn create_vk_surface(
+        &mut self,
+        _viewport: &mut Viewport,
+        _instance: u64,
+        _out_surface: &mut u64,
+    ) -> i32 {
+        0
+    }
+}
+
+// Enables viewports for the ImGui context of the given window, returning the state shared with
+// its platform backend. The context must be active.
+pub(crate) fn enable_viewports(
+    ctx: &mut imgui::Context,
+    window: &Window,
+) -> Rc<RefCell<ViewportState>> {
+    let state = Rc::new(RefCell::new(ViewportState::default()));
+    ctx.set_platform_backend(ImguiViewportBackend(state.clone()));
+
+    let io = ctx.io_mut();
+    io.config_flags.insert(ConfigFlags::VIEWPORTS_ENABLE);
+    io.backend_flags
+        .insert(BackendFlags::PLATFORM_HAS_VIEWPORTS | BackendFlags::RENDERER_HAS_VIEWPORTS);
+
+    // ImGui requires at least one monitor, so start with the monitor of the owning window
+    // until the monitors are known
+    let position = window_position(window, None).unwrap_or_default();
+    set_monitors(ctx, std::iter::empty(), window, position);
+    state
+}
+
+// The desktop position of a window's client area, and the offset of the client area from the
+// outer position of the window (i.e. the size of its decorations), in physical pixels
+#[derive(Clone, Copy)]
+struct ClientArea {
+    position: IVec2,
+    inset: IVec2,
+}
+
+// Bevy only knows the outer position of each window (and only once the window has been moved),
+// whereas ImGui places viewports by their client area, so this is queried from winit where the
+// platform supports it
+fn client_area(winit_windows: Option<&WinitWindows>, entity: Entity) -> Option<ClientArea> {
+    let window = winit_windows?.get_window(entity)?;
+    let inner = window.inner_position().ok()?;
+    let outer = window.outer_position().ok()?;
+    Some(ClientArea {
+        position: IVec2::new(inner.x, inner.y),
+        inset: IVec2::new(inner.x - outer.x, inner.y - outer.y),
+    })
+}
+
+// Returns the desktop position of the client area of the given window in logical pixels, if
+// known, falling back to the outer position of the window where winit cannot provide it
+fn window_position(window: &Window, client_area: Option<ClientArea>) -> Option<Vec2> {
+    let position = match (client_area, window.position) {
+        (Some(client_area), _) => client_area.position,
+        (None, WindowPosition::At(position)) => position,
+        _ => return None,
+    };
+    Some(position.as_vec2() / window.scale_factor())
+}
+
+// Tells ImGui about the monitors that its viewports can be placed on, falling back to an area
+// that covers the owning window (at the given position) if there are none. The context must be
+// active.
+fn set_monitors<'a>(
+    ctx: &mut imgui::Context,
+    monitors: impl Iterator<Item = &'a Monitor>,
+    owner: &Window,
+    owner_position: Vec2,
+) {
+    let scale_factor = owner.scale_factor();
+    let area = |position: Vec2, size: Vec2, dpi_scale: f32| {
+        // Zero any fields that are not set below, such as the platform handle
+        let mut monitor: PlatformMonitor = unsafe { std::mem::zeroed() };
+        monitor.main_pos = position.into();
+        monitor.main_size = size.into();
+        monitor.work_pos = position.into();
+        monitor.work_size = size.into();
+        monitor.dpi_scale = dpi_scale;
+        monitor
+    };
+
+    let mut platform_monitors: Vec<_> = monitors
+        .map(|monitor| {
+            area(
+                monitor.physical_position.as_vec2() / scale_factor,
+                UVec2::new(monitor.physical_width, monitor.physical_height).as_vec2()
+                    / scale_factor,
+                monitor.scale_factor as f32,
+            )
+        })
+        .collect();
+    if platform_monitors.is_empty() {
+        platform_monitors.push(area(
+            owner_position,
+            Vec2::new(owner.width(), owner.height()),
+            scale_factor,
+        ));
+    }
+    ctx.platform_io_mut()
+        .monitors
+        .replace_from_slice(&platform_monitors);
+}
+
+// Collects the viewport windows of each ImGui context, so that their input is routed to it
+pub(crate) fn viewport_input<'a>(
+    context: &ImguiContext,
+    windows: impl Iterator<Item = (Entity, &'a Window, &'a ImguiViewportWindow)>,
+) -> ViewportInput {
+    let mut input = ViewportInput::default();
+    for (entity, window, viewport_window) in windows {
+        let origin = context
+            .targets
+            .get(&viewport_window.owner)
+            .and_then(|target_context| target_context.viewports.as_ref())
+            .and_then(|viewports| viewports.borrow().viewport_position(entity))
+            .unwrap_or_default();
+        input
+            .windows
+            .insert(entity, (viewport_window.owner, origin));
+        if let Some(cursor_position) = window.cursor_position() {
+            input
+                .cursor_positions
+                .insert(viewport_window.owner, cursor_position + origin);
+        }
+        if window.focused {
+            input.focused.insert(viewport_window.owner);
+        }
+    }
+    input
+}
+
+// Ends the platform frame of an ImGui context after it has been rendered, which creates, updates
+// and destroys the platform windows of its viewports, and stores the draw data of each viewport
+// for Extract. The context must be active.
+pub(crate) fn update_platform_windows(
+    ctx: &mut imgui::Context,
+    viewports: &RefCell<ViewportState>,
+) {
+    ctx.update_platform_windows();
+
+    let mut state = viewports.borrow_mut();
+    for viewport in ctx.viewports() {
+        let Some(window) = state.viewports.get_mut(&viewport_id(viewport)) else {
+            continue; // The main viewport is drawn with the owning window's draw data
+        };
+        // Viewports that were not rendered this frame have no draw data
+        let raw = viewport as *const Viewport as *const imgui::sys::ImGuiViewport;
+        window.draw_data = if unsafe { (*raw).DrawData.is_null() } {
+            OwnedDrawData::default()
+        } else {
+            OwnedDrawData::from(viewport.draw_data())
+        };
+    }
+}
+
+// Spawns and despawns the windows of ImGui viewports, applies the changes that ImGui makes to
+// each viewport to its window, and records the changes made to each window by the platform
+#[allow(clippy::type_complexity)]
+pub(crate) fn imgui_update_viewports_system(
+    mut commands: Commands,
+    context: NonSend<ImguiContext>,
+    mut windows: Query<(Entity, &mut Window, Option<&ImguiViewportWindow>)>,
+    monitors: Query<&Monitor>,
+    winit_windows: Option<NonSend<WinitWindows>>,
+) {
+    let winit_windows = winit_windows.as_deref();
+
+    // Viewport windows are despawned along with the context that owns them
+    for (entity, _, viewport_window) in &windows {
+        if viewport_window
+            .is_some_and(|viewport_window| !context.targets.contains_key(&viewport_window.owner))
+        {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for (owner, target_context) in context.targets.iter() {
+        let Some(viewports) = &target_context.viewports else {
+            continue;
+        };
+        let Ok((_, owner_window, _)) = windows.get(*owner) else {
+            continue;
+        };
+        let scale_factor = owner_window.scale_factor();
+
+        let mut state = viewports.borrow_mut();
+        // The last known position is kept until the position of the window is known
+        if let Some(position) = window_position(owner_window, client_area(winit_windows, *owner)) {
+            state.owner_position = position;
+        }
+
+        target_context.activate();
+        set_monitors(
+            &mut target_context.ctx.write().unwrap(),
+            monitors.iter(),
+            owner_window,
+            state.owner_position,
+        );
+        state.owner_size = Vec2::new(owner_window.width(), owner_window.height());
+        state.owner_focused = owner_window.focused;
+
+        for entity in std::mem::take(&mut state.destroyed) {
+            if windows.contains(entity) {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        for viewport in state.viewports.values_mut() {
+            let window = viewport
+                .entity
+                .and_then(|entity| windows.get_mut(entity).ok());
+            match window {
+                Some((entity, mut window, _)) => {
+                    let client_area = client_area(winit_windows, entity);
+                    update_viewport_window(viewport, &mut window, client_area, scale_factor);
+                }
+                None if viewport.entity.is_some() => {
+                    // The window was closed by the platform, which is reported to ImGui. If
+                    // ImGui keeps the viewport open, its window is spawned again.
+                    viewport.entity = None;
+                    viewport.platform_closed = true;
+                }
+                None if !viewport.platform_closed => {
+                    viewport.entity = Some(spawn_viewport_window(
+                        &mut commands,
+                        viewport,
+                        *owner,
+                        scale_factor,
+                    ));
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+// Viewport windows use the scale factor of the owning window, as ImGui renders every viewport
+// with the font atlas and display scale of the owning window
+fn spawn_viewport_window(
+    commands: &mut Commands,
+    viewport: &mut ViewportWindow,
+    owner: Entity,
+    scale_factor: f32,
+) -> Entity {
+    let entity = commands
+        .spawn((
+            Window {
+                title: viewport.title.clone(),
+                position: WindowPosition::At((viewport.position * scale_factor).as_ivec2()),
+                resolution: WindowResolution::new(viewport.size.x, viewport.size.y)
+                    .with_scale_factor_override(scale_factor),
+                decorations: viewport.decorations,
+                focused: viewport.focused,
+                skip_taskbar: !viewport.taskbar_icon,
+                window_level: if viewport.top_most {
+                    WindowLevel::AlwaysOnTop
+                } else {
+                    WindowLevel::Normal
+                },
+                ..default()
+            },
+            ImguiViewportWindow { owner },
+        ))
+        .id();
+
+    // ImGui is drawn on top of the last camera that renders to each window
+    commands.spawn((
+        Camera2d,
+        Camera {
+            target: RenderTarget::Window(WindowRef::Entity(entity)),
+            ..default()
+        },
+        ChildOf(entity),
+    ));
+
+    viewport.position_changed = false;
+    viewport.size_changed = false;
+    viewport.title_changed = false;
+    viewport.focus_requested = false;
+    entity
+}
+
+fn update_viewport_window(
+    viewport: &mut ViewportWindow,
+    window: &mut Window,
+    client_area: Option<ClientArea>,
+    scale_factor: f32,
+) {
+    if window.resolution.scale_factor_override() != Some(scale_factor) {
+        window
+            .resolution
+            .set_scale_factor_override(Some(scale_factor));
+    }
+
+    // Changes made by ImGui take precedence over those made by the platform
+    if std::mem::take(&mut viewport.position_changed) {
+        // Bevy positions the outside of the window, so this is offset by its decorations
+        let inset = client_area.map_or(IVec2::ZERO, |client_area| client_area.inset);
+        window.position = WindowPosition::At((viewport.position * scale_factor).as_ivec2() - inset);
+    } else if let Some(position) = window_position(window, client_area) {
+        if position.distance(viewport.position) >= 1.0 {
+            viewport.position = position;
+            viewport.platform_moved = true;
+        }
+    }
+
+    let size = Vec2::new(window.width(), window.height());
+    if std::mem::take(&mut viewport.size_changed) {
+        window.resolution.set(viewport.size.x, viewport.size.y);
+    } else if size.distance(viewport.size) >= 1.0 {
+        viewport.size = size;
+        viewport.platform_resized = true;
+    }
+
+    if std::mem::take(&mut viewport.title_changed) {
+        window.title = viewport.title.clone();
+    }
+
+    if std::mem::take(&mut viewport.focus_requested) {
+        window.focused = true;
+    }
+    viewport.focused = window.focused;
+}
+
+// Prepares the render state of the viewport windows of the given ImGui context. Each viewport
+// window has its own renderer, which uses the font atlas and textures of the context.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn extract_viewports(
+    context: &mut ImguiRenderContext,
+    target_context: &ImguiTargetContext,
+    viewports: &RefCell<ViewportState>,
+    textures: &HashMap<TextureId, Arc<StrongHandle>>,
+    texture_modify: &ImguiTextureModifyState,
+    font_atlas_changed: bool,
+    display_scale: f32,
+    extracted_windows: &ExtractedWindows,
+    device: &RenderDevice,
+    queue: &RenderQueue,
+    extracted: &mut HashSet<Entity>,
+) {
+    let mut state = viewports.borrow_mut();
+    for viewport in state.viewports.values_mut() {
+        let Some(entity) = viewport.entity else {
+            continue;
+        };
+        let Some(texture_format) = extracted_windows
+            .windows
+            .get(&entity)
+            .and_then(|extracted_window| extracted_window.swap_chain_texture_format)
+        else {
+            continue; // The window has not been created yet
+        };
+        extracted.insert(entity);
+
+        let previous = context.targets.get(&entity);
+        let recreated = previous.is_none_or(|previous| {
+            texture_format != previous.texture_format || display_scale != previous.display_scale
+        });
+        if recreated {
+            target_context.activate();
+            let renderer = create_renderer(
+                &context.plugin,
+                texture_format,
+                &mut target_context.ctx.write().unwrap(),
+                device,
+                queue,
+            );
+            context.targets.insert(
+                entity,
+                ImguiTargetRenderContext {
+                    target: ImguiRenderTarget::Window,
+                    renderer: RwLock::new(renderer),
+                    texture_format,
+                    draw: OwnedDrawDataWrap::default(),
+                    display_scale,
+                    // Re-add all textures
+                    textures_to_add: textures.clone(),
+                    textures_to_remove: Vec::new(),
+                },
+            );
+        }
+
+        let target_render_context = context.targets.get_mut(&entity).unwrap();
+        if font_atlas_changed && !recreated {
+            target_context.activate();
+            target_render_context
+                .renderer
+                .get_mut()
+                .unwrap()
+                .reload_font_texture(
+                    &mut target_context.ctx.write().unwrap(),
+                    device.wgpu_device(),
+                    queue,
+                );
+        }
+        if !recreated {
+            // Just add the textures that have been registered this frame
+            for texture_id in texture_modify.to_add.iter() {
+                target_render_context
+                    .textures_to_add
+                    .insert(*texture_id, textures[texture_id].clone());
+            }
+        }
+        target_render_context
+            .textures_to_remove
+            .extend_from_slice(&texture_modify.to_remove);
+        target_render_context.draw = OwnedDrawDataWrap(std::mem::take(&mut viewport.draw_data));
+    }
+}